@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type AverageMode = { "type": "infinite" } | { "type": "exponential", "data": number } | { "type": "window", "data": number } | { "type": "frames", "data": number };
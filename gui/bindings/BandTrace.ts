@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BandLevel } from "./BandLevel";
+import type { InputBus } from "./InputBus";
+import type { TraceChannel } from "./TraceChannel";
+
+export type BandTrace = { bus: InputBus, channel: TraceChannel, bands: Array<BandLevel>, };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChannelGroup } from "./ChannelGroup";
+
+export type ChannelSource = { "type": "perChannel" } | { "type": "left" } | { "type": "right" } | { "type": "mid" } | { "type": "side" } | { "type": "sum" } | { "type": "difference" } | { "type": "max" } | { "type": "channel", "data": number } | { "type": "group", "data": ChannelGroup };
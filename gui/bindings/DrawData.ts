@@ -1,3 +1,14 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BandTrace } from "./BandTrace";
+import type { CoherenceData } from "./CoherenceData";
+import type { LevelData } from "./LevelData";
+import type { LoudnessData } from "./LoudnessData";
+import type { PitchReading } from "./PitchReading";
+import type { ReferenceTrace } from "./ReferenceTrace";
+import type { SpectralDescriptors } from "./SpectralDescriptors";
+import type { SpectralPeak } from "./SpectralPeak";
+import type { SpectrogramData } from "./SpectrogramData";
+import type { SpectrumDisplay } from "./SpectrumDisplay";
+import type { SpectrumTrace } from "./SpectrumTrace";
 
-export type DrawData = { "type": "spectrum", "data": Array<[number, number]> };
+export type DrawData = { "type": "spectrum", "data": Array<SpectrumTrace> } | { "type": "bands", "data": Array<BandTrace> } | { "type": "spectrogram", "data": SpectrogramData } | { "type": "references", "data": Array<ReferenceTrace> } | { "type": "peaks", "data": Array<SpectralPeak> } | { "type": "pitch", "data": PitchReading | null } | { "type": "loudness", "data": LoudnessData } | { "type": "levels", "data": LevelData } | { "type": "correlation", "data": number | null } | { "type": "goniometer", "data": Array<[number, number]> } | { "type": "coherence", "data": CoherenceData | null } | { "type": "descriptors", "data": Array<SpectralDescriptors> } | { "type": "display", "data": SpectrumDisplay };
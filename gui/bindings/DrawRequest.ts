@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type DrawRequest = { "type": "spectrum" };
+export type DrawRequest = { "type": "spectrum" } | { "type": "bands" } | { "type": "spectrogram" } | { "type": "references" } | { "type": "peaks" } | { "type": "pitch" } | { "type": "loudness" } | { "type": "levels" } | { "type": "correlation" } | { "type": "goniometer" } | { "type": "coherence" } | { "type": "descriptors" } | { "type": "display" };
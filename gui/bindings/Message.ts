@@ -1,6 +1,9 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { DrawData } from "./DrawData";
 import type { DrawRequest } from "./DrawRequest";
+import type { MeterCommand } from "./MeterCommand";
+import type { MeterConfigUpdate } from "./MeterConfigUpdate";
+import type { SpectrumAnalyzerCommand } from "./SpectrumAnalyzerCommand";
 import type { SpectrumAnalyzerConfigUpdate } from "./SpectrumAnalyzerConfigUpdate";
 
-export type Message = { "type": "init" } | { "type": "resize", "data": { width: number, height: number, } } | { "type": "drawData", "data": DrawData } | { "type": "drawRequest", "data": DrawRequest } | { "type": "spectrumAnalyzerConfigUpdate", "data": SpectrumAnalyzerConfigUpdate };
+export type Message = { "type": "init" } | { "type": "resize", "data": { width: number, height: number, } } | { "type": "drawData", "data": DrawData } | { "type": "drawRequest", "data": DrawRequest } | { "type": "spectrumAnalyzerConfigUpdate", "data": SpectrumAnalyzerConfigUpdate } | { "type": "spectrumAnalyzerCommand", "data": SpectrumAnalyzerCommand } | { "type": "meterCommand", "data": MeterCommand } | { "type": "meterConfigUpdate", "data": MeterConfigUpdate };
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TraceChannel } from "./TraceChannel";
+
+export type ReferenceTrace = { name: string, channel: TraceChannel, points: Array<[number, number]>, difference: Array<[number, number]> | null, };
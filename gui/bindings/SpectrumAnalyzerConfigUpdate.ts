@@ -1,4 +1,15 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AverageMode } from "./AverageMode";
+import type { Calibration } from "./Calibration";
+import type { ChannelSource } from "./ChannelSource";
+import type { FrequencyScale } from "./FrequencyScale";
+import type { MagnitudeUnit } from "./MagnitudeUnit";
 import type { MonitorMode } from "./MonitorMode";
+import type { OctaveFraction } from "./OctaveFraction";
+import type { Overlap } from "./Overlap";
+import type { PeakFall } from "./PeakFall";
+import type { SlopeMode } from "./SlopeMode";
+import type { Weighting } from "./Weighting";
+import type { WindowFunction } from "./WindowFunction";
 
-export type SpectrumAnalyzerConfigUpdate = { "type": "fps", "data": number } | { "type": "monitorMode", "data": MonitorMode } | { "type": "decaySpeed", "data": number } | { "type": "interpolate", "data": boolean };
+export type SpectrumAnalyzerConfigUpdate = { "type": "fps", "data": number } | { "type": "monitorMode", "data": MonitorMode } | { "type": "decaySpeed", "data": number } | { "type": "interpolate", "data": boolean } | { "type": "channelSource", "data": ChannelSource } | { "type": "fftSize", "data": number } | { "type": "window", "data": WindowFunction } | { "type": "overlap", "data": Overlap } | { "type": "sidechain", "data": boolean } | { "type": "multiResolution", "data": boolean } | { "type": "octaveFraction", "data": OctaveFraction } | { "type": "spectrogramSpan", "data": number } | { "type": "spectrogramRows", "data": number } | { "type": "peakHold", "data": boolean } | { "type": "peakHoldTime", "data": number } | { "type": "peakFall", "data": PeakFall } | { "type": "average", "data": boolean } | { "type": "averageMode", "data": AverageMode } | { "type": "peakCount", "data": number } | { "type": "pitchTracker", "data": boolean } | { "type": "referencePitch", "data": number } | { "type": "correlationTime", "data": number } | { "type": "coherence", "data": boolean } | { "type": "coherenceTime", "data": number } | { "type": "weighting", "data": Weighting } | { "type": "slope", "data": number } | { "type": "slopeMode", "data": SlopeMode } | { "type": "slopePivot", "data": number } | { "type": "frequencyScale", "data": FrequencyScale } | { "type": "magnitudeUnit", "data": MagnitudeUnit } | { "type": "calibration", "data": Calibration };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FrequencyScale } from "./FrequencyScale";
+import type { MagnitudeUnit } from "./MagnitudeUnit";
+import type { Weighting } from "./Weighting";
+
+export type SpectrumDisplay = { frequencyScale: FrequencyScale, frequencyRange: [number, number], grid: Array<[number, number]>, weighting: Weighting, unit: MagnitudeUnit, fullScale: number, magnitudeRange: [number, number], };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { InputBus } from "./InputBus";
+import type { TraceChannel } from "./TraceChannel";
+import type { TraceLayer } from "./TraceLayer";
+
+export type SpectrumTrace = { bus: InputBus, channel: TraceChannel, layer: TraceLayer, points: Array<[number, number]>, };
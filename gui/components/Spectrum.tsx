@@ -1,6 +1,7 @@
 'use client';
 
 import { Message } from '@/bindings/Message';
+import { SpectrumTrace } from '@/bindings/SpectrumTrace';
 import { usePluginListener } from '@/hooks/usePluginListener';
 
 import { useCallback, useEffect, useRef } from 'react';
@@ -12,12 +13,20 @@ export function Spectrum(props: {
 	height?: number;
 	fill: boolean;
 	antiAliasing: boolean;
+	// draw the right channel upside down, below the left channel
+	mirrored?: boolean;
 	style: string | CanvasGradient | CanvasPattern;
+	sidechainStyle?: string | CanvasGradient | CanvasPattern;
+	peakHoldStyle?: string | CanvasGradient | CanvasPattern;
+	averageStyle?: string | CanvasGradient | CanvasPattern;
 	className?: string;
 }) {
-	const { fill, antiAliasing, style, width, height, fps } = props;
+	const { fill, antiAliasing, style, width, height, fps, mirrored } = props;
+	const sidechainStyle = props.sidechainStyle ?? 'rgb(160,160,160)';
+	const peakHoldStyle = props.peakHoldStyle ?? 'rgb(255,200,80)';
+	const averageStyle = props.averageStyle ?? 'rgb(80,200,255)';
 
-	const coordinatesToDraw = useRef<[number, number][]>([]);
+	const tracesToDraw = useRef<SpectrumTrace[]>([]);
 	const listener = useCallback((m: Message) => {
 		if (m.type !== 'drawData') {
 			return;
@@ -26,7 +35,7 @@ export function Spectrum(props: {
 			return;
 		}
 		const spectrumData = m.data.data;
-		coordinatesToDraw.current = spectrumData;
+		tracesToDraw.current = spectrumData;
 		// console.log(spectrumData);
 	}, []);
 	usePluginListener(listener);
@@ -43,20 +52,50 @@ export function Spectrum(props: {
 		const height = ctx.canvas.height;
 		const width = ctx.canvas.width;
 
-		ctx.strokeStyle = style;
-		ctx.fillStyle = style;
 		ctx.clearRect(0, 0, width, height);
 		ctx.lineWidth = 1;
 
-		//
-		const spectrumArray = coordinatesToDraw.current;
+		for (const trace of tracesToDraw.current) {
+			const isLive = trace.layer.type === 'live';
+			const traceStyle =
+				trace.layer.type === 'peakHold'
+					? peakHoldStyle
+					: trace.layer.type === 'average'
+					? averageStyle
+					: trace.bus.type === 'sidechain'
+					? sidechainStyle
+					: style;
+			ctx.strokeStyle = traceStyle;
+			ctx.fillStyle = traceStyle;
+
+			const flip =
+				mirrored &&
+				trace.channel.type === 'channel' &&
+				trace.channel.data === 1;
+			// held peaks and averages are only ever drawn as lines over the live trace
+			drawTrace(ctx, trace.points, flip, fill && isLive);
+		}
+	}
+
+	function drawTrace(
+		ctx: CanvasRenderingContext2D,
+		spectrumArray: [number, number][],
+		flip: boolean | undefined,
+		fillTrace: boolean
+	) {
+		const height = ctx.canvas.height;
+		const width = ctx.canvas.width;
+		// when mirroring, each trace gets half of the canvas
+		const traceHeight = mirrored ? height / 2 : height;
 
 		ctx.beginPath();
 		for (let i = 0; i < spectrumArray.length; i++) {
 			const [x, y] = spectrumArray[i];
 
 			let scaledX = x * width;
-			let scaledY = (1.0 - y) * height;
+			let scaledY = flip
+				? traceHeight + y * traceHeight
+				: (1.0 - y) * traceHeight;
 
 			if (!antiAliasing) {
 				scaledX = Math.floor(scaledX);
@@ -65,9 +104,9 @@ export function Spectrum(props: {
 			ctx.lineTo(scaledX, scaledY);
 		}
 
-		if (fill) {
-			ctx.lineTo(width, height);
-			ctx.lineTo(0, height);
+		if (fillTrace) {
+			ctx.lineTo(width, traceHeight);
+			ctx.lineTo(0, traceHeight);
 			ctx.fill();
 		}
 
@@ -0,0 +1,247 @@
+//! Lock-free transfer of captured audio from the audio thread to the editor.
+
+use std::sync::{
+    atomic::{AtomicU32, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+// how much audio the ring can hold before the editor has to read it
+const CAPACITY_SECONDS: f32 = 0.5;
+
+/// A wait-free, single-producer single-consumer ring of interleaved frames.
+///
+/// The audio thread writes whole blocks with [`SampleRing::push_block`], and the editor reads
+/// everything that's available with [`SampleRing::pop`]. If a block doesn't fit, it's dropped
+/// entirely (and counted as an overrun), so the reader never sees partially overwritten audio.
+pub struct SampleRing {
+    channels: usize,
+    // in frames, always a power of two
+    capacity: usize,
+    // f32 bits, so we don't need any unsafe code
+    data: Box<[AtomicU32]>,
+
+    // these only ever increase (wrapping), and are masked to index into `data`
+    write_pos: AtomicUsize,
+    read_pos: AtomicUsize,
+
+    overruns: AtomicUsize,
+}
+
+impl SampleRing {
+    pub fn new(channels: usize, capacity: usize) -> Self {
+        let capacity = capacity.max(1).next_power_of_two();
+        let data = (0..capacity * channels)
+            .map(|_| AtomicU32::new(0))
+            .collect();
+
+        Self {
+            channels,
+            capacity,
+            data,
+            write_pos: AtomicUsize::new(0),
+            read_pos: AtomicUsize::new(0),
+            overruns: AtomicUsize::new(0),
+        }
+    }
+
+    /// Create a ring that holds roughly half a second of audio.
+    pub fn for_sample_rate(channels: usize, sample_rate: f32) -> Self {
+        Self::new(channels, (sample_rate * CAPACITY_SECONDS) as usize)
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Write `num_samples` frames from planar channel buffers. Missing channels are written as silence.
+    ///
+    /// Returns `false` if the block didn't fit and was dropped.
+    pub fn push_block<C: AsRef<[f32]>>(&self, block: &[C], num_samples: usize) -> bool {
+        let write = self.write_pos.load(Ordering::Relaxed);
+        let read = self.read_pos.load(Ordering::Acquire);
+        let free = self.capacity - write.wrapping_sub(read);
+
+        if num_samples > free {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        for i in 0..num_samples {
+            let frame = (write.wrapping_add(i) & (self.capacity - 1)) * self.channels;
+            for channel in 0..self.channels {
+                let sample = block.get(channel).map_or(0.0, |c| c.as_ref()[i]);
+                self.data[frame + channel].store(sample.to_bits(), Ordering::Relaxed);
+            }
+        }
+
+        self.write_pos
+            .store(write.wrapping_add(num_samples), Ordering::Release);
+        true
+    }
+
+    /// Read every available frame, oldest first. Returns the number of frames read.
+    pub fn pop(&self, mut callback: impl FnMut(&[f32])) -> usize {
+        let read = self.read_pos.load(Ordering::Relaxed);
+        let write = self.write_pos.load(Ordering::Acquire);
+        let available = write.wrapping_sub(read);
+
+        let mut frame = vec![0.0; self.channels];
+        for i in 0..available {
+            let start = (read.wrapping_add(i) & (self.capacity - 1)) * self.channels;
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = f32::from_bits(self.data[start + channel].load(Ordering::Relaxed));
+            }
+            callback(&frame);
+        }
+
+        self.read_pos.store(write, Ordering::Release);
+        available
+    }
+
+    /// Throw away everything that hasn't been read yet, and reset the overrun count.
+    ///
+    /// Only the reader should call this.
+    pub fn discard(&self) {
+        let write = self.write_pos.load(Ordering::Acquire);
+        self.read_pos.store(write, Ordering::Release);
+        self.overruns.store(0, Ordering::Relaxed);
+    }
+
+    /// How many blocks were dropped because the reader fell behind.
+    pub fn overruns(&self) -> usize {
+        self.overruns.load(Ordering::Relaxed)
+    }
+}
+
+/// One ring per input bus.
+pub struct Capture {
+    pub main: SampleRing,
+    pub sidechain: SampleRing,
+}
+
+impl Capture {
+    pub fn new(main_channels: usize, sidechain_channels: usize, sample_rate: f32) -> Self {
+        Self {
+            main: SampleRing::for_sample_rate(main_channels, sample_rate),
+            sidechain: SampleRing::for_sample_rate(sidechain_channels, sample_rate),
+        }
+    }
+
+    pub fn discard(&self) {
+        self.main.discard();
+        self.sidechain.discard();
+    }
+
+    pub fn overruns(&self) -> usize {
+        self.main.overruns() + self.sidechain.overruns()
+    }
+}
+
+/// Shares the current [`Capture`] with the editor.
+///
+/// The plugin creates new rings whenever it's (re)initialized, since their size depends on the
+/// sample rate. Only the editor and `initialize()` lock this, never `process()`.
+#[derive(Clone)]
+pub struct CaptureHandle(Arc<Mutex<Arc<Capture>>>);
+
+impl CaptureHandle {
+    pub fn new(capture: Arc<Capture>) -> Self {
+        Self(Arc::new(Mutex::new(capture)))
+    }
+
+    pub fn replace(&self, capture: Arc<Capture>) {
+        *self.0.lock().unwrap() = capture;
+    }
+
+    pub fn current(&self) -> Arc<Capture> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pop everything, as (left, right) pairs.
+    fn pop_all(ring: &SampleRing) -> Vec<(f32, f32)> {
+        let mut frames = Vec::new();
+        ring.pop(|frame| frames.push((frame[0], frame[1])));
+        frames
+    }
+
+    fn ramp(start: usize, length: usize) -> Vec<f32> {
+        (start..start + length).map(|i| i as f32).collect()
+    }
+
+    #[test]
+    fn capacity_is_rounded_up_to_a_power_of_two() {
+        let ring = SampleRing::new(2, 5);
+        let block = [ramp(0, 8), ramp(0, 8)];
+        assert!(ring.push_block(&block, 8));
+        assert!(!ring.push_block(&block, 1));
+    }
+
+    #[test]
+    fn frames_come_out_in_order() {
+        let ring = SampleRing::new(2, 8);
+        assert!(ring.push_block(&[ramp(0, 3), ramp(10, 3)], 3));
+        assert_eq!(pop_all(&ring), vec![(0.0, 10.0), (1.0, 11.0), (2.0, 12.0)]);
+        assert_eq!(ring.pop(|_| {}), 0);
+    }
+
+    #[test]
+    fn missing_channels_are_silent() {
+        let ring = SampleRing::new(2, 8);
+        assert!(ring.push_block(&[ramp(1, 2)], 2));
+        assert_eq!(pop_all(&ring), vec![(1.0, 0.0), (2.0, 0.0)]);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let ring = SampleRing::new(2, 8);
+        for round in 0..5 {
+            let start = round * 6;
+            assert!(ring.push_block(&[ramp(start, 6), ramp(start + 100, 6)], 6));
+            let frames = pop_all(&ring);
+            let expected: Vec<(f32, f32)> = (start..start + 6)
+                .map(|i| (i as f32, (i + 100) as f32))
+                .collect();
+            assert_eq!(frames, expected);
+        }
+        assert_eq!(ring.overruns(), 0);
+    }
+
+    #[test]
+    fn blocks_that_dont_fit_are_dropped_whole() {
+        let ring = SampleRing::new(2, 8);
+        assert!(ring.push_block(&[ramp(0, 6), ramp(0, 6)], 6));
+        // only 2 frames are free
+        assert!(!ring.push_block(&[ramp(50, 3), ramp(50, 3)], 3));
+        assert!(!ring.push_block(&[ramp(50, 3), ramp(50, 3)], 3));
+        assert_eq!(ring.overruns(), 2);
+
+        // what was already there is untouched, and there's still room for a smaller block
+        assert!(ring.push_block(&[ramp(6, 2), ramp(6, 2)], 2));
+        let frames = pop_all(&ring);
+        assert_eq!(frames.len(), 8);
+        assert!(frames
+            .iter()
+            .enumerate()
+            .all(|(i, frame)| frame.0 == i as f32));
+    }
+
+    #[test]
+    fn discard_drops_unread_frames_and_overruns() {
+        let ring = SampleRing::new(2, 8);
+        assert!(ring.push_block(&[ramp(0, 8), ramp(0, 8)], 8));
+        assert!(!ring.push_block(&[ramp(0, 1), ramp(0, 1)], 1));
+
+        ring.discard();
+        assert_eq!(ring.overruns(), 0);
+        assert_eq!(ring.pop(|_| {}), 0);
+
+        // and the whole ring is free again
+        assert!(ring.push_block(&[ramp(20, 8), ramp(20, 8)], 8));
+        assert_eq!(pop_all(&ring)[0], (20.0, 20.0));
+    }
+}
@@ -1,15 +1,12 @@
-use crossbeam_channel::Sender;
 use fundsp::hacker32::*;
 
-pub fn build_graph(tx: Sender<f32>) -> Box<dyn AudioUnit> {
-    let watcher = join::<U2>()
-    // TODO: use fundsp::snoop
-        >> map(move |i| {
-            let _ = tx.try_send(i[0]);
-            i[0]
-        })
-        >> sink();
-
-    let graph = multipass::<U2>() ^ watcher;
-    Box::new(graph)
+pub fn build_graph(num_channels: usize) -> Box<dyn AudioUnit> {
+    match num_channels {
+        1 => Box::new(multipass::<U1>()),
+        2 => Box::new(multipass::<U2>()),
+        6 => Box::new(multipass::<U6>()),
+        8 => Box::new(multipass::<U8>()),
+        12 => Box::new(multipass::<U12>()),
+        _ => unreachable!("no audio layout has {num_channels} channels"),
+    }
 }
@@ -1,7 +1,15 @@
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::editor::spectrum_analyzer::ipc::SpectrumAnalyzerConfigUpdate;
+use crate::editor::{
+    meters::ipc::{MeterCommand, MeterConfigUpdate},
+    spectrum_analyzer::{
+        ipc::{SpectrumAnalyzerCommand, SpectrumAnalyzerConfigUpdate},
+        scale::FrequencyScale,
+        units::MagnitudeUnit,
+        weighting::Weighting,
+    },
+};
 
 #[derive(Serialize, Deserialize, TS, Debug)]
 #[serde(rename_all = "camelCase", tag = "type", content = "data")]
@@ -12,16 +20,229 @@ pub enum Message {
     DrawData(DrawData),
     DrawRequest(DrawRequest),
     SpectrumAnalyzerConfigUpdate(SpectrumAnalyzerConfigUpdate),
+    SpectrumAnalyzerCommand(SpectrumAnalyzerCommand),
+    MeterCommand(MeterCommand),
+    MeterConfigUpdate(MeterConfigUpdate),
 }
 #[derive(Serialize, Deserialize, TS, Debug)]
 #[serde(rename_all = "camelCase", tag = "type", content = "data")]
 #[ts(export)]
 pub enum DrawData {
-    Spectrum(Vec<(f32, f32)>),
+    Spectrum(Vec<SpectrumTrace>),
+    Bands(Vec<BandTrace>),
+    Spectrogram(SpectrogramData),
+    References(Vec<ReferenceTrace>),
+    Peaks(Vec<SpectralPeak>),
+    // nothing when there's no clear pitch (or the tracker is off)
+    Pitch(Option<PitchReading>),
+    Loudness(LoudnessData),
+    Levels(LevelData),
+    // from -1 to 1, or nothing for silence
+    Correlation(Option<f32>),
+    // (mid, side) points since the last request, oldest first
+    Goniometer(Vec<(f32, f32)>),
+    // nothing when coherence is off
+    Coherence(Option<CoherenceData>),
+    // oldest first, with one reading for every spectrum draw (that wasn't silent)
+    Descriptors(Vec<SpectralDescriptors>),
+    // how the spectrum's levels are currently being shown
+    Display(SpectrumDisplay),
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SpectrumTrace {
+    pub bus: InputBus,
+    pub channel: TraceChannel,
+    pub layer: TraceLayer,
+    pub points: Vec<(f32, f32)>,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ReferenceTrace {
+    pub name: String,
+    pub channel: TraceChannel,
+    pub points: Vec<(f32, f32)>,
+    // live minus reference, if that channel is being drawn;
+    // the levels are in db (centered on 0), not normalized
+    pub difference: Option<Vec<(f32, f32)>>,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BandTrace {
+    pub bus: InputBus,
+    pub channel: TraceChannel,
+    pub bands: Vec<BandLevel>,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct BandLevel {
+    // hz
+    pub center: f32,
+    // normalized positions of the band edges
+    pub start: f32,
+    pub end: f32,
+    pub level: f32,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SpectrogramData {
+    pub columns_per_second: f32,
+    pub rows: usize,
+    // only the columns added since the last request, oldest first;
+    // each goes from the lowest to the highest frequency, with normalized levels
+    pub columns: Vec<Vec<f32>>,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SpectralPeak {
+    // hz
+    pub frequency: f32,
+    // in the current unit
+    pub level: f32,
+    // normalized position on the frequency axis
+    pub position: f32,
+    // the nearest note, like "A4"
+    pub note: String,
+    // how far the peak is from that note
+    pub cents: f32,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct PitchReading {
+    // hz
+    pub frequency: f32,
+    pub note: String,
+    pub cents: f32,
+    // 0 to 1
+    pub confidence: f32,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SpectrumDisplay {
+    // positions on the frequency axis map to frequencies by spacing them evenly on this scale
+    // (between the ends of the range) and mapping them back to hz
+    pub frequency_scale: FrequencyScale,
+    // hz
+    pub frequency_range: (f32, f32),
+    // (hz, position) for round frequencies, to draw grid lines at
+    pub grid: Vec<(f32, f32)>,
+    pub weighting: Weighting,
+    // every level sent to the GUI (and the magnitude range) is in this unit
+    pub unit: MagnitudeUnit,
+    // what a full-scale sine reads in the unit
+    pub full_scale: f32,
+    // the levels at the bottom and top of the display
+    pub magnitude_range: (f32, f32),
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct SpectralDescriptors {
+    // hz, the power-weighted mean frequency
+    pub centroid: f32,
+    // 0 (a single tone) to 1 (white noise)
+    pub flatness: f32,
+    // hz, below which 85% of the power lies
+    pub rolloff: f32,
+    // db, the strongest bin above the mean
+    pub crest: f32,
+    // db/oct, the slope of a line fitted through the spectrum (pink noise is -3)
+    pub tilt: f32,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct CoherenceData {
+    // (position, value) points on the same frequency axis as the spectrum
+    // magnitude-squared coherence, from 0 (unrelated) to 1 (one is a filtered copy of the other)
+    pub coherence: Vec<(f32, f32)>,
+    // radians, from -pi to pi (positive when the left channel leads)
+    pub phase: Vec<(f32, f32)>,
+    // db, the level of the mono sum relative to the channels (very negative where it cancels)
+    pub mono_loss: Vec<(f32, f32)>,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct LoudnessData {
+    // lufs, or nothing if there isn't enough audio above the gates yet
+    pub momentary: Option<f32>,
+    pub short_term: Option<f32>,
+    pub integrated: Option<f32>,
+    // lu
+    pub range: Option<f32>,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct LevelData {
+    // dbtp
+    pub ceiling: f32,
+    pub channels: Vec<ChannelLevels>,
+}
+#[derive(Serialize, Deserialize, TS, Debug)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct ChannelLevels {
+    // dbfs/dbtp, since the last request
+    pub sample_peak: f32,
+    pub true_peak: f32,
+    // since the last reset
+    pub sample_peak_hold: f32,
+    pub true_peak_hold: f32,
+    // samples over the ceiling since the last reset
+    pub overs: usize,
+}
+#[derive(Serialize, Deserialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum InputBus {
+    Main,
+    Sidechain,
+}
+#[derive(Serialize, Deserialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum TraceChannel {
+    // an input channel by index (0 is left, 1 is right)
+    Channel(usize),
+    // a signal derived from several channels (mid, side, max...)
+    Mix,
+}
+#[derive(Serialize, Deserialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum TraceLayer {
+    // the smoothed spectrum
+    Live,
+    // the loudest (unsmoothed) level each bin has reached recently
+    PeakHold,
+    // the long-term average
+    Average,
 }
 #[derive(Serialize, Deserialize, TS, Debug)]
 #[serde(rename_all = "camelCase", tag = "type", content = "data")]
 #[ts(export)]
 pub enum DrawRequest {
     Spectrum,
+    Bands,
+    Spectrogram,
+    References,
+    Peaks,
+    Pitch,
+    Loudness,
+    Levels,
+    Correlation,
+    Goniometer,
+    Coherence,
+    Descriptors,
+    Display,
 }
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Deserialize, Serialize, TS, Debug)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum MeterCommand {
+    // start measuring the integrated loudness and loudness range from scratch
+    ResetLoudness,
+    // clear the held peaks and the overs
+    ResetPeaks,
+}
+
+#[derive(Deserialize, Serialize, TS, Debug)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum MeterConfigUpdate {
+    // dbtp, anything above this counts as an over
+    TruePeakCeiling(f32),
+}
@@ -0,0 +1,67 @@
+pub mod ipc;
+
+use nih_plug::{prelude::AtomicF32, util::gain_to_db};
+use std::sync::{atomic::Ordering, Arc};
+
+use crate::{
+    editor::ipc::{ChannelLevels, LevelData, LoudnessData},
+    meters::{loudness::LoudnessReadings, true_peak::PeakReadings},
+};
+
+/// Reads the meters that run on the audio thread.
+pub struct MetersHelper {
+    loudness: Arc<LoudnessReadings>,
+    peaks: Arc<PeakReadings>,
+}
+
+impl MetersHelper {
+    pub fn new(loudness: Arc<LoudnessReadings>, peaks: Arc<PeakReadings>) -> Self {
+        Self { loudness, peaks }
+    }
+
+    pub fn reset_peaks(&self) {
+        self.peaks.reset();
+    }
+
+    pub fn set_true_peak_ceiling(&self, ceiling: f32) {
+        self.peaks.ceiling.store(ceiling, Ordering::Relaxed);
+    }
+
+    pub fn reset_loudness(&self) {
+        self.loudness.request_reset();
+    }
+
+    pub fn handle_loudness_request(&self) -> LoudnessData {
+        // readings are negative infinity until there's something to measure
+        let read = |reading: &AtomicF32| {
+            let value = reading.load(Ordering::Relaxed);
+            value.is_finite().then_some(value)
+        };
+        LoudnessData {
+            momentary: read(&self.loudness.momentary),
+            short_term: read(&self.loudness.short_term),
+            integrated: read(&self.loudness.integrated),
+            range: read(&self.loudness.range),
+        }
+    }
+
+    pub fn handle_level_request(&self) -> LevelData {
+        let num_channels = self.peaks.num_channels.load(Ordering::Relaxed);
+        let channels = self.peaks.channels[..num_channels]
+            .iter()
+            .map(|channel| ChannelLevels {
+                // the audio thread starts over after each read
+                sample_peak: gain_to_db(channel.sample_peak.swap(0.0, Ordering::Relaxed)),
+                true_peak: gain_to_db(channel.true_peak.swap(0.0, Ordering::Relaxed)),
+                sample_peak_hold: gain_to_db(channel.sample_peak_hold.load(Ordering::Relaxed)),
+                true_peak_hold: gain_to_db(channel.true_peak_hold.load(Ordering::Relaxed)),
+                overs: channel.overs.load(Ordering::Relaxed),
+            })
+            .collect();
+
+        LevelData {
+            ceiling: self.peaks.ceiling.load(Ordering::Relaxed),
+            channels,
+        }
+    }
+}
@@ -1,14 +1,18 @@
 mod embedded;
 mod ipc;
+mod meters;
 mod spectrum_analyzer;
 
 #[cfg(feature = "embedded-gui")]
 use embedded::build_protocol;
 
 use ipc::{DrawData, DrawRequest, Message};
+use meters::{
+    ipc::{MeterCommand, MeterConfigUpdate},
+    MetersHelper,
+};
 use spectrum_analyzer::SpectrumAnalyzerHelper;
 
-use crossbeam_channel::Receiver;
 use nih_plug::{editor::Editor, prelude::AtomicF32};
 use nih_plug_webview::{
     Context, EditorHandler, WebViewConfig, WebViewEditor, WebViewSource, WebViewState,
@@ -16,17 +20,24 @@ use nih_plug_webview::{
 use serde_json::json;
 use std::{path::PathBuf, sync::Arc};
 
-use crate::editor::spectrum_analyzer::ipc::SpectrumAnalyzerConfigUpdate;
+use crate::{
+    capture::CaptureHandle,
+    editor::spectrum_analyzer::ipc::{SpectrumAnalyzerCommand, SpectrumAnalyzerConfigUpdate},
+    meters::{loudness::LoudnessReadings, true_peak::PeakReadings},
+};
 
 pub struct PluginGui {
     spectrum_analyzer: SpectrumAnalyzerHelper,
+    meters: MetersHelper,
 }
 
 impl PluginGui {
     pub fn new_editor(
         state: &Arc<WebViewState>,
-        rx: Receiver<f32>,
+        capture: CaptureHandle,
         sample_rate: Arc<AtomicF32>,
+        loudness: Arc<LoudnessReadings>,
+        peaks: Arc<PeakReadings>,
     ) -> Option<Box<dyn Editor>> {
         // SOURCE
         let protocol_name = "assets".to_string();
@@ -51,7 +62,8 @@ impl PluginGui {
         };
         // EDITOR
         let editor_base = PluginGui {
-            spectrum_analyzer: SpectrumAnalyzerHelper::new(sample_rate, rx.clone()),
+            spectrum_analyzer: SpectrumAnalyzerHelper::new(sample_rate, capture),
+            meters: MetersHelper::new(loudness, peaks),
         };
 
         Some(Box::new(WebViewEditor::new_with_webview(
@@ -81,6 +93,9 @@ impl PluginGui {
             // !!
             Message::DrawRequest(draw_request) => self.handle_draw_request(draw_request, cx),
             Message::SpectrumAnalyzerConfigUpdate(update) => self.handle_config_update(update, cx),
+            Message::SpectrumAnalyzerCommand(command) => self.handle_command(command, cx),
+            Message::MeterCommand(command) => self.handle_meter_command(command, cx),
+            Message::MeterConfigUpdate(update) => self.handle_meter_config_update(update, cx),
 
             // still not sure what to do here
             Message::DrawData(_) => todo!(),
@@ -90,8 +105,68 @@ impl PluginGui {
     fn handle_draw_request(&mut self, draw_request: DrawRequest, cx: &mut Context) {
         match draw_request {
             DrawRequest::Spectrum => {
-                let coordinates = self.spectrum_analyzer.handle_draw_request();
-                let message = Message::DrawData(DrawData::Spectrum(coordinates));
+                let traces = self.spectrum_analyzer.handle_draw_request();
+                let message = Message::DrawData(DrawData::Spectrum(traces));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Bands => {
+                let traces = self.spectrum_analyzer.handle_band_request();
+                let message = Message::DrawData(DrawData::Bands(traces));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Spectrogram => {
+                let data = self.spectrum_analyzer.handle_spectrogram_request();
+                let message = Message::DrawData(DrawData::Spectrogram(data));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::References => {
+                let traces = self.spectrum_analyzer.handle_reference_request();
+                let message = Message::DrawData(DrawData::References(traces));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Peaks => {
+                let peaks = self.spectrum_analyzer.handle_peak_request();
+                let message = Message::DrawData(DrawData::Peaks(peaks));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Pitch => {
+                let pitch = self.spectrum_analyzer.handle_pitch_request();
+                let message = Message::DrawData(DrawData::Pitch(pitch));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Loudness => {
+                let loudness = self.meters.handle_loudness_request();
+                let message = Message::DrawData(DrawData::Loudness(loudness));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Levels => {
+                let levels = self.meters.handle_level_request();
+                let message = Message::DrawData(DrawData::Levels(levels));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Correlation => {
+                let correlation = self.spectrum_analyzer.handle_correlation_request();
+                let message = Message::DrawData(DrawData::Correlation(correlation));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Goniometer => {
+                let points = self.spectrum_analyzer.handle_goniometer_request();
+                let message = Message::DrawData(DrawData::Goniometer(points));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Coherence => {
+                let coherence = self.spectrum_analyzer.handle_coherence_request();
+                let message = Message::DrawData(DrawData::Coherence(coherence));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Descriptors => {
+                let descriptors = self.spectrum_analyzer.handle_descriptor_request();
+                let message = Message::DrawData(DrawData::Descriptors(descriptors));
+                cx.send_message(json!(message).to_string());
+            }
+            DrawRequest::Display => {
+                let display = self.spectrum_analyzer.handle_display_request();
+                let message = Message::DrawData(DrawData::Display(display));
                 cx.send_message(json!(message).to_string());
             }
         }
@@ -111,6 +186,123 @@ impl PluginGui {
             SpectrumAnalyzerConfigUpdate::Interpolate(n) => {
                 self.spectrum_analyzer.config.interpolate = n;
             }
+            SpectrumAnalyzerConfigUpdate::ChannelSource(source) => {
+                self.spectrum_analyzer.set_channel_source(source);
+            }
+            SpectrumAnalyzerConfigUpdate::FftSize(size) => {
+                self.spectrum_analyzer.set_fft_size(size);
+            }
+            SpectrumAnalyzerConfigUpdate::Window(window) => {
+                self.spectrum_analyzer.set_window(window);
+            }
+            SpectrumAnalyzerConfigUpdate::Overlap(overlap) => {
+                self.spectrum_analyzer.set_overlap(overlap);
+            }
+            SpectrumAnalyzerConfigUpdate::Sidechain(enabled) => {
+                self.spectrum_analyzer.set_sidechain(enabled);
+            }
+            SpectrumAnalyzerConfigUpdate::MultiResolution(enabled) => {
+                self.spectrum_analyzer.set_multi_resolution(enabled);
+            }
+            SpectrumAnalyzerConfigUpdate::OctaveFraction(fraction) => {
+                self.spectrum_analyzer.set_octave_fraction(fraction);
+            }
+            SpectrumAnalyzerConfigUpdate::SpectrogramSpan(seconds) => {
+                self.spectrum_analyzer.set_spectrogram_span(seconds);
+            }
+            SpectrumAnalyzerConfigUpdate::SpectrogramRows(rows) => {
+                self.spectrum_analyzer.set_spectrogram_rows(rows);
+            }
+            SpectrumAnalyzerConfigUpdate::PeakHold(enabled) => {
+                self.spectrum_analyzer.set_peak_hold(enabled);
+            }
+            SpectrumAnalyzerConfigUpdate::PeakHoldTime(seconds) => {
+                self.spectrum_analyzer.set_peak_hold_time(seconds);
+            }
+            SpectrumAnalyzerConfigUpdate::PeakFall(fall) => {
+                self.spectrum_analyzer.set_peak_fall(fall);
+            }
+            SpectrumAnalyzerConfigUpdate::Average(enabled) => {
+                self.spectrum_analyzer.set_average(enabled);
+            }
+            SpectrumAnalyzerConfigUpdate::AverageMode(mode) => {
+                self.spectrum_analyzer.set_average_mode(mode);
+            }
+            SpectrumAnalyzerConfigUpdate::PeakCount(count) => {
+                self.spectrum_analyzer.set_peak_count(count);
+            }
+            SpectrumAnalyzerConfigUpdate::PitchTracker(enabled) => {
+                self.spectrum_analyzer.set_pitch_tracker(enabled);
+            }
+            SpectrumAnalyzerConfigUpdate::ReferencePitch(frequency) => {
+                self.spectrum_analyzer.set_reference_pitch(frequency);
+            }
+            SpectrumAnalyzerConfigUpdate::CorrelationTime(seconds) => {
+                self.spectrum_analyzer.set_correlation_time(seconds);
+            }
+            SpectrumAnalyzerConfigUpdate::Coherence(enabled) => {
+                self.spectrum_analyzer.set_coherence(enabled);
+            }
+            SpectrumAnalyzerConfigUpdate::CoherenceTime(seconds) => {
+                self.spectrum_analyzer.set_coherence_time(seconds);
+            }
+            SpectrumAnalyzerConfigUpdate::Weighting(weighting) => {
+                self.spectrum_analyzer.set_weighting(weighting);
+            }
+            SpectrumAnalyzerConfigUpdate::Slope(slope) => {
+                self.spectrum_analyzer.set_slope(slope);
+            }
+            SpectrumAnalyzerConfigUpdate::SlopeMode(mode) => {
+                self.spectrum_analyzer.set_slope_mode(mode);
+            }
+            SpectrumAnalyzerConfigUpdate::SlopePivot(frequency) => {
+                self.spectrum_analyzer.set_slope_pivot(frequency);
+            }
+            SpectrumAnalyzerConfigUpdate::FrequencyScale(scale) => {
+                self.spectrum_analyzer.set_frequency_scale(scale);
+            }
+            SpectrumAnalyzerConfigUpdate::MagnitudeUnit(unit) => {
+                self.spectrum_analyzer.set_magnitude_unit(unit);
+            }
+            SpectrumAnalyzerConfigUpdate::Calibration(calibration) => {
+                self.spectrum_analyzer.set_calibration(calibration);
+            }
+        }
+    }
+
+    fn handle_command(&mut self, command: SpectrumAnalyzerCommand, _: &mut Context) {
+        match command {
+            SpectrumAnalyzerCommand::ResetPeakHold => {
+                self.spectrum_analyzer.reset_peak_hold();
+            }
+            SpectrumAnalyzerCommand::ResetAverage => {
+                self.spectrum_analyzer.reset_average();
+            }
+            SpectrumAnalyzerCommand::CaptureReference(name) => {
+                self.spectrum_analyzer.capture_reference(name);
+            }
+            SpectrumAnalyzerCommand::RemoveReference(name) => {
+                self.spectrum_analyzer.remove_reference(&name);
+            }
+        }
+    }
+
+    fn handle_meter_command(&mut self, command: MeterCommand, _: &mut Context) {
+        match command {
+            MeterCommand::ResetLoudness => {
+                self.meters.reset_loudness();
+            }
+            MeterCommand::ResetPeaks => {
+                self.meters.reset_peaks();
+            }
+        }
+    }
+
+    fn handle_meter_config_update(&mut self, update: MeterConfigUpdate, _: &mut Context) {
+        match update {
+            MeterConfigUpdate::TruePeakCeiling(ceiling) => {
+                self.meters.set_true_peak_ceiling(ceiling);
+            }
         }
     }
 }
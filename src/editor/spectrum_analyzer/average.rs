@@ -0,0 +1,213 @@
+//! Long-term spectrum averages, for a stable picture of the overall tonal balance.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use ts_rs::TS;
+
+// windowed averages are kept as the sums of at most this many blocks of frames,
+// so their memory doesn't grow with their length
+const WINDOW_BLOCKS: usize = 32;
+
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum AverageMode {
+    // every frame since the last reset is weighted equally
+    Infinite,
+    // older frames fade out with the given time constant (seconds)
+    Exponential(f32),
+    // the linear average of the last few seconds
+    Window(f32),
+    // the linear average of the last N frames
+    Frames(usize),
+}
+
+enum State {
+    Infinite {
+        sums: Vec<f64>,
+        count: usize,
+    },
+    Exponential {
+        // how much of each new frame gets mixed in
+        alpha: f32,
+        powers: Vec<f32>,
+        initialized: bool,
+    },
+    Window {
+        // frames per block
+        block_length: usize,
+        // how many full blocks make up the window
+        num_blocks: usize,
+        // the power sums of the most recent full blocks, oldest first
+        blocks: VecDeque<Vec<f64>>,
+        // of every full block
+        sums: Vec<f64>,
+        // the block that's still being filled
+        current: Vec<f64>,
+        current_length: usize,
+    },
+}
+
+/// A per-bin average of the power of every frame pushed into it.
+pub struct Average {
+    num_bins: usize,
+    state: State,
+}
+
+impl Average {
+    /// `frame_rate` is how many frames get pushed every second.
+    pub fn new(mode: AverageMode, num_bins: usize, frame_rate: f32) -> Self {
+        let state = match mode {
+            AverageMode::Infinite => State::Infinite {
+                sums: vec![0.0; num_bins],
+                count: 0,
+            },
+            AverageMode::Exponential(time_constant) => State::Exponential {
+                alpha: smoothing_factor(frame_rate, time_constant),
+                powers: vec![0.0; num_bins],
+                initialized: false,
+            },
+            AverageMode::Window(seconds) => {
+                Self::window((seconds * frame_rate).ceil() as usize, num_bins)
+            }
+            AverageMode::Frames(length) => Self::window(length, num_bins),
+        };
+
+        Self { num_bins, state }
+    }
+
+    /// The window covers the last `length` frames, plus the ones in the block that's being filled
+    /// (so up to 1/32 longer, for long windows).
+    fn window(length: usize, num_bins: usize) -> State {
+        let length = length.max(1);
+        let block_length = length.div_ceil(WINDOW_BLOCKS);
+        let num_blocks = length.div_ceil(block_length);
+        State::Window {
+            block_length,
+            num_blocks,
+            blocks: VecDeque::with_capacity(num_blocks),
+            sums: vec![0.0; num_bins],
+            current: vec![0.0; num_bins],
+            current_length: 0,
+        }
+    }
+
+    /// Add a frame of (linear) bin levels.
+    pub fn push(&mut self, levels: &[f32]) {
+        match &mut self.state {
+            State::Infinite { sums, count } => {
+                for (sum, level) in sums.iter_mut().zip(levels) {
+                    *sum += level.powi(2) as f64;
+                }
+                *count += 1;
+            }
+            State::Exponential {
+                alpha,
+                powers,
+                initialized,
+            } => {
+                // start from the first frame, instead of fading in from silence
+                let alpha = if *initialized { *alpha } else { 1.0 };
+                for (power, level) in powers.iter_mut().zip(levels) {
+                    *power += alpha * (level.powi(2) - *power);
+                }
+                *initialized = true;
+            }
+            State::Window {
+                block_length,
+                num_blocks,
+                blocks,
+                sums,
+                current,
+                current_length,
+            } => {
+                for (sum, level) in current.iter_mut().zip(levels) {
+                    *sum += level.powi(2) as f64;
+                }
+                *current_length += 1;
+                if *current_length < *block_length {
+                    return;
+                }
+
+                // the oldest block's buffer is reused for the next one
+                let mut next = if blocks.len() == *num_blocks {
+                    let oldest = blocks.pop_front().unwrap();
+                    for (sum, power) in sums.iter_mut().zip(&oldest) {
+                        *sum -= power;
+                    }
+                    oldest
+                } else {
+                    vec![0.0; self.num_bins]
+                };
+                next.fill(0.0);
+                for (sum, power) in sums.iter_mut().zip(current.iter()) {
+                    *sum += power;
+                }
+                std::mem::swap(current, &mut next);
+                blocks.push_back(next);
+                *current_length = 0;
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match &mut self.state {
+            State::Infinite { sums, count } => {
+                sums.fill(0.0);
+                *count = 0;
+            }
+            State::Exponential {
+                powers,
+                initialized,
+                ..
+            } => {
+                powers.fill(0.0);
+                *initialized = false;
+            }
+            State::Window {
+                blocks,
+                sums,
+                current,
+                current_length,
+                ..
+            } => {
+                blocks.clear();
+                sums.fill(0.0);
+                current.fill(0.0);
+                *current_length = 0;
+            }
+        }
+    }
+
+    /// The averaged (linear) bin levels.
+    pub fn levels(&self) -> Vec<f32> {
+        match &self.state {
+            State::Infinite { sums, count } => sums
+                .iter()
+                .map(|sum| (sum / (*count).max(1) as f64).sqrt() as f32)
+                .collect(),
+            State::Exponential { powers, .. } => powers.iter().map(|power| power.sqrt()).collect(),
+            State::Window {
+                block_length,
+                blocks,
+                sums,
+                current,
+                current_length,
+                ..
+            } => {
+                let count = (blocks.len() * block_length + current_length).max(1) as f64;
+                sums.iter()
+                    .zip(current)
+                    // the running sums can drift slightly below zero
+                    .map(|(sum, current)| ((sum + current).max(0.0) / count).sqrt() as f32)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// How much of each new value a one-pole smoother mixes in, for updates arriving at `rate` (hz)
+/// and a time constant in seconds.
+pub fn smoothing_factor(rate: f32, time_constant: f32) -> f32 {
+    1.0 - (-1.0 / (time_constant * rate).max(1.0)).exp()
+}
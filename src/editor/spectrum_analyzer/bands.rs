@@ -0,0 +1,109 @@
+//! Fractional-octave bands, as used by RTAs.
+//!
+//! Center frequencies and band edges follow the base-ten system from IEC 61260-1 (ANSI S1.11).
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+const REFERENCE_FREQUENCY: f32 = 1000.0; // hz
+
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum OctaveFraction {
+    Octave,
+    Half,
+    Third,
+    Sixth,
+    Twelfth,
+    TwentyFourth,
+}
+
+impl OctaveFraction {
+    /// The number of bands per octave.
+    pub fn bands_per_octave(self) -> u32 {
+        match self {
+            OctaveFraction::Octave => 1,
+            OctaveFraction::Half => 2,
+            OctaveFraction::Third => 3,
+            OctaveFraction::Sixth => 6,
+            OctaveFraction::Twelfth => 12,
+            OctaveFraction::TwentyFourth => 24,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OctaveBand {
+    pub center: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+/// Every band whose center frequency lies within the given range.
+pub fn octave_bands(fraction: OctaveFraction, (min_freq, max_freq): (f32, f32)) -> Vec<OctaveBand> {
+    let b = fraction.bands_per_octave();
+    // the "octave" is a ratio of 10^(3/10), not exactly 2
+    let octave_ratio = 10f32.powf(0.3);
+    let half_band = octave_ratio.powf(1.0 / (2 * b) as f32);
+
+    let center = |x: i32| {
+        // odd fractions have a band centered on 1 kHz, even ones have an edge there
+        let exponent = if b % 2 == 1 {
+            x as f32 / b as f32
+        } else {
+            (2 * x + 1) as f32 / (2 * b) as f32
+        };
+        REFERENCE_FREQUENCY * octave_ratio.powf(exponent)
+    };
+
+    let lowest = ((min_freq / REFERENCE_FREQUENCY).log(octave_ratio) * b as f32).floor() as i32 - 1;
+    let highest = ((max_freq / REFERENCE_FREQUENCY).log(octave_ratio) * b as f32).ceil() as i32 + 1;
+
+    (lowest..=highest)
+        .map(center)
+        .filter(|center| (min_freq..=max_freq).contains(center))
+        .map(|center| OctaveBand {
+            center,
+            low: center / half_band,
+            high: center * half_band,
+        })
+        .collect()
+}
+
+/// Sum the energy of the FFT bins that fall into each band.
+///
+/// Bins that straddle a band edge are split proportionally, so bands narrower than a bin
+/// still get a sensible level. The result is scaled so a sine reads the same as it does on the
+/// spectrum, regardless of the window's noise bandwidth (`enbw`, in bins).
+pub fn band_levels(
+    spectrum: &[f32],
+    bands: &[OctaveBand],
+    sample_rate: f32,
+    enbw: f32,
+) -> Vec<f32> {
+    if sample_rate <= 0.0 {
+        return vec![0.0; bands.len()];
+    }
+    let last_bin = spectrum.len() - 1;
+    let bin_width = sample_rate / (last_bin * 2) as f32;
+
+    bands
+        .iter()
+        .map(|band| {
+            let first = ((band.low / bin_width - 0.5).floor().max(0.0) as usize).min(last_bin);
+            let last = ((band.high / bin_width + 0.5).ceil() as usize).min(last_bin);
+
+            let mut power = 0.0;
+            for (k, level) in spectrum.iter().enumerate().take(last + 1).skip(first) {
+                // each bin covers half a bin width on either side of its center
+                let bin_low = (k as f32 - 0.5) * bin_width;
+                let bin_high = (k as f32 + 0.5) * bin_width;
+                let overlap =
+                    (band.high.min(bin_high) - band.low.max(bin_low)).max(0.0) / bin_width;
+                power += overlap * level.powi(2);
+            }
+            (power / enbw).sqrt()
+        })
+        .collect()
+}
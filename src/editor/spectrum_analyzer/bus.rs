@@ -0,0 +1,179 @@
+use crate::editor::{
+    ipc::{TraceChannel, TraceLayer},
+    spectrum_analyzer::{
+        average::{Average, AverageMode},
+        bands::OctaveBand,
+        channel::{frame_rate, ChannelAnalyzer, ChannelSource},
+        config::SpectrumAnalyzerConfig,
+        monitor::Monitor,
+        peak_hold::PeakHold,
+    },
+};
+
+/// Analyzes every signal needed from a single input bus (main or sidechain).
+pub struct BusAnalyzer {
+    source: ChannelSource,
+    channels: Vec<ChannelAnalyzer>,
+    peak_hold: bool,
+    // seconds between frames
+    frame_period: f32,
+}
+
+impl BusAnalyzer {
+    pub fn new(
+        config: &SpectrumAnalyzerConfig,
+        num_channels: usize,
+        sample_rate: f32,
+        monitor: &Monitor,
+    ) -> Self {
+        let source = config.channel_source;
+        let channels = source
+            .inputs(num_channels)
+            .into_iter()
+            .map(|input| ChannelAnalyzer::new(input, config, sample_rate, monitor))
+            .collect();
+
+        Self {
+            source,
+            channels,
+            peak_hold: config.peak_hold,
+            frame_period: 1.0 / frame_rate(config, sample_rate),
+        }
+    }
+
+    /// Returns `true` if a new frame was computed. Every channel computes frames at the same time.
+    pub fn tick(&mut self, frame: &[f32]) -> bool {
+        let mut new_frame = false;
+        for channel in self.channels.iter_mut() {
+            new_frame |= channel.tick(frame);
+        }
+
+        // averages and peak holds are updated every frame, not just when the GUI asks for them,
+        // so nothing that happens between draws is missed
+        let averaging = self
+            .channels
+            .iter()
+            .any(|channel| channel.average.is_some());
+        if new_frame && (averaging || self.peak_hold) {
+            for (_, analyzer, spectrum) in self.raw_traces() {
+                let analyzer = &mut self.channels[analyzer];
+                if let Some(average) = &mut analyzer.average {
+                    average.push(&spectrum);
+                }
+                if self.peak_hold {
+                    analyzer.peak_hold.update(&spectrum, self.frame_period);
+                }
+            }
+        }
+        new_frame
+    }
+
+    pub fn monitors_mut(&mut self) -> impl Iterator<Item = &mut Monitor> {
+        self.channels.iter_mut().flat_map(|channel| {
+            channel
+                .monitors
+                .iter_mut()
+                .chain(channel.band_monitors.iter_mut())
+        })
+    }
+
+    pub fn peak_holds_mut(&mut self) -> impl Iterator<Item = &mut PeakHold> {
+        self.channels
+            .iter_mut()
+            .map(|channel| &mut channel.peak_hold)
+    }
+
+    pub fn averages_mut(&mut self) -> impl Iterator<Item = &mut Average> {
+        self.channels
+            .iter_mut()
+            .filter_map(|channel| channel.average.as_mut())
+    }
+
+    pub fn set_peak_hold(&mut self, enabled: bool) {
+        self.peak_hold = enabled;
+    }
+
+    pub fn set_average(&mut self, mode: Option<AverageMode>, frame_rate: f32) {
+        for channel in self.channels.iter_mut() {
+            channel.set_average(mode, frame_rate);
+        }
+    }
+
+    /// The latest unsmoothed spectrum of each trace, along with the analyzer whose monitors it uses.
+    fn raw_traces(&self) -> Vec<(TraceChannel, usize, Vec<f32>)> {
+        match self.source {
+            ChannelSource::PerChannel => self
+                .channels
+                .iter()
+                .enumerate()
+                .map(|(i, channel)| (TraceChannel::Channel(i), i, channel.raw_spectrum()))
+                .collect(),
+            ChannelSource::Max => {
+                let mut max = self.channels[0].raw_spectrum();
+                for channel in &self.channels[1..] {
+                    for (m, level) in max.iter_mut().zip(channel.raw_spectrum()) {
+                        *m = m.max(level);
+                    }
+                }
+                vec![(TraceChannel::Mix, 0, max)]
+            }
+            ChannelSource::Left => {
+                vec![(TraceChannel::Channel(0), 0, self.channels[0].raw_spectrum())]
+            }
+            ChannelSource::Right => {
+                vec![(TraceChannel::Channel(1), 0, self.channels[0].raw_spectrum())]
+            }
+            ChannelSource::Channel(i) => {
+                vec![(TraceChannel::Channel(i), 0, self.channels[0].raw_spectrum())]
+            }
+            _ => vec![(TraceChannel::Mix, 0, self.channels[0].raw_spectrum())],
+        }
+    }
+
+    /// The per-bin maximum of every trace's latest unsmoothed spectrum.
+    pub fn raw_max(&self) -> Vec<f32> {
+        let mut traces = self.raw_traces().into_iter();
+        let (_, _, mut max) = traces.next().unwrap();
+        for (_, _, spectrum) in traces {
+            for (m, level) in max.iter_mut().zip(spectrum) {
+                *m = m.max(level);
+            }
+        }
+        max
+    }
+
+    /// Advance the monitors by one frame and return the smoothed (linear) bin levels of each trace.
+    ///
+    /// Each trace is followed by its held (unsmoothed) peaks if peak hold is enabled, and then by
+    /// its average if that's enabled.
+    pub fn get_bin_levels(&mut self) -> Vec<(TraceChannel, TraceLayer, Vec<f32>)> {
+        let mut traces = Vec::new();
+        for (channel, analyzer, spectrum) in self.raw_traces() {
+            let analyzer = &mut self.channels[analyzer];
+            let levels = analyzer.smooth(&spectrum);
+            traces.push((channel, TraceLayer::Live, levels));
+            if self.peak_hold {
+                let held = analyzer.peak_hold.levels().to_vec();
+                traces.push((channel, TraceLayer::PeakHold, held));
+            }
+            if let Some(average) = &analyzer.average {
+                traces.push((channel, TraceLayer::Average, average.levels()));
+            }
+        }
+        traces
+    }
+
+    /// Advance the band monitors by one frame and return the smoothed (linear) band levels of each trace.
+    pub fn get_band_levels(&mut self) -> Vec<(TraceChannel, Vec<f32>)> {
+        self.raw_traces()
+            .into_iter()
+            .map(|(channel, analyzer, spectrum)| {
+                (channel, self.channels[analyzer].smooth_bands(&spectrum))
+            })
+            .collect()
+    }
+
+    pub fn bands(&self) -> &[OctaveBand] {
+        self.channels[0].bands()
+    }
+}
@@ -0,0 +1,254 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use ts_rs::TS;
+
+use crate::editor::spectrum_analyzer::{
+    average::{Average, AverageMode},
+    bands::{band_levels, octave_bands, OctaveBand},
+    config::SpectrumAnalyzerConfig,
+    monitor::Monitor,
+    peak_hold::PeakHold,
+    processing::blend_resolutions,
+    stft::Stft,
+    MAX_FFT_SIZE, MIN_FFT_SIZE,
+};
+
+// which signal (or signals) get analyzed
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum ChannelSource {
+    // every channel is analyzed and drawn independently
+    PerChannel,
+    Left,
+    Right,
+    // (L + R) / 2
+    Mid,
+    // (L - R) / 2
+    Side,
+    // L + R
+    Sum,
+    // L - R
+    Difference,
+    // per-bin maximum of every channel's spectrum
+    Max,
+    // a single channel, by index
+    Channel(usize),
+    // the sum of a group of channels
+    Group(ChannelGroup),
+}
+
+// groups of channels in a surround layout,
+// assuming the usual L R C LFE Ls Rs (Lrs Rrs) (heights...) ordering
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum ChannelGroup {
+    All,
+    Fronts,
+    Center,
+    Lfe,
+    Surrounds,
+    Heights,
+}
+
+impl ChannelGroup {
+    /// The channel indices in this group, for a layout with `num_channels` channels.
+    pub fn channels(self, num_channels: usize) -> Range<usize> {
+        // mono and stereo only have fronts
+        if num_channels <= 2 {
+            return match self {
+                ChannelGroup::All | ChannelGroup::Fronts => 0..num_channels,
+                _ => 0..0,
+            };
+        }
+        let range = match self {
+            ChannelGroup::All => 0..num_channels,
+            ChannelGroup::Fronts => 0..2,
+            ChannelGroup::Center => 2..3,
+            ChannelGroup::Lfe => 3..4,
+            // 5.1 has 2 surrounds, 7.1 (and up) has 4
+            ChannelGroup::Surrounds => 4..num_channels.min(8),
+            ChannelGroup::Heights => 8..num_channels,
+        };
+        range.start.min(num_channels)..range.end.min(num_channels)
+    }
+}
+
+impl ChannelSource {
+    /// The signals that each need their own FFT for this source.
+    pub fn inputs(self, num_channels: usize) -> Vec<ChannelSource> {
+        match self {
+            ChannelSource::PerChannel | ChannelSource::Max => {
+                (0..num_channels).map(ChannelSource::Channel).collect()
+            }
+            source => vec![source],
+        }
+    }
+
+    /// Derive a single sample from a frame with any number of channels.
+    ///
+    /// `PerChannel` and `Max` are combined per-bin after the FFT, so they can't be derived here.
+    pub fn derive(self, frame: &[f32]) -> f32 {
+        let channel = |i: usize| frame.get(i).copied().unwrap_or(0.0);
+        let (left, right) = front_pair(frame);
+
+        match self {
+            ChannelSource::Left => left,
+            ChannelSource::Right => right,
+            ChannelSource::Mid => (left + right) * 0.5,
+            ChannelSource::Side => (left - right) * 0.5,
+            ChannelSource::Sum => left + right,
+            ChannelSource::Difference => left - right,
+            ChannelSource::Channel(i) => channel(i),
+            ChannelSource::Group(group) => group.channels(frame.len()).map(channel).sum(),
+            ChannelSource::PerChannel | ChannelSource::Max => {
+                unreachable!("{self:?} can't be derived from a single frame")
+            }
+        }
+    }
+}
+
+/// The front left and right samples of a frame. Mono layouts use the same channel for both.
+pub fn front_pair(frame: &[f32]) -> (f32, f32) {
+    let left = frame.first().copied().unwrap_or(0.0);
+    let right = frame.get(1).copied().unwrap_or(left);
+    (left, right)
+}
+
+/// The FFT size of each resolution, largest first.
+pub fn fft_sizes(config: &SpectrumAnalyzerConfig) -> Vec<usize> {
+    if config.multi_resolution {
+        // large windows for the bass, medium for the mids, small for the highs
+        vec![
+            (config.fft_size * 4).min(MAX_FFT_SIZE),
+            config.fft_size,
+            (config.fft_size / 4).max(MIN_FFT_SIZE),
+        ]
+    } else {
+        vec![config.fft_size]
+    }
+}
+
+/// How many frames are computed every second.
+///
+/// With several resolutions, a frame is computed whenever the fastest (smallest) FFT computes one.
+pub fn frame_rate(config: &SpectrumAnalyzerConfig, sample_rate: f32) -> f32 {
+    let smallest_fft_size = *fft_sizes(config).last().unwrap();
+    sample_rate / config.overlap.hop(smallest_fft_size) as f32
+}
+
+/// FFT and smoothing state for a single analyzed signal.
+pub struct ChannelAnalyzer {
+    source: ChannelSource,
+    // one per resolution, largest first
+    stfts: Vec<Stft>,
+    sample_rate: f32,
+    pub monitors: Vec<Monitor>,
+
+    bands: Vec<OctaveBand>,
+    pub band_monitors: Vec<Monitor>,
+
+    pub peak_hold: PeakHold,
+    // only when averaging is enabled
+    pub average: Option<Average>,
+}
+
+impl ChannelAnalyzer {
+    pub fn new(
+        source: ChannelSource,
+        config: &SpectrumAnalyzerConfig,
+        sample_rate: f32,
+        monitor: &Monitor,
+    ) -> Self {
+        let fft_sizes = fft_sizes(config);
+        // everything ends up on the grid of the largest FFT
+        let num_bins = fft_sizes[0] / 2 + 1;
+        let monitors = vec![monitor.clone(); num_bins];
+
+        let stfts = fft_sizes
+            .into_iter()
+            .map(|fft_size| Stft::new(fft_size, config.window, config.overlap))
+            .collect();
+
+        let bands = octave_bands(config.octave_fraction, config.frequency_range);
+        let band_monitors = vec![monitor.clone(); bands.len()];
+
+        let peak_hold = PeakHold::new(num_bins, config.peak_hold_time, config.peak_fall);
+        let average = config.average.then(|| {
+            Average::new(
+                config.average_mode,
+                num_bins,
+                frame_rate(config, sample_rate),
+            )
+        });
+
+        Self {
+            source,
+            stfts,
+            sample_rate,
+            monitors,
+            bands,
+            band_monitors,
+            peak_hold,
+            average,
+        }
+    }
+
+    /// Returns `true` if any of the FFTs computed a new frame.
+    pub fn tick(&mut self, frame: &[f32]) -> bool {
+        // the source is derived here, so the FFT only ever sees a single signal
+        let sample = self.source.derive(frame);
+        let mut new_frame = false;
+        for stft in self.stfts.iter_mut() {
+            new_frame |= stft.push(sample);
+        }
+        new_frame
+    }
+
+    /// Replace the average (if any) with a new, empty one.
+    pub fn set_average(&mut self, mode: Option<AverageMode>, frame_rate: f32) {
+        let num_bins = self.monitors.len();
+        self.average = mode.map(|mode| Average::new(mode, num_bins, frame_rate));
+    }
+
+    /// The latest (unsmoothed, linear) bin levels.
+    pub fn raw_spectrum(&self) -> Vec<f32> {
+        if let [stft] = self.stfts.as_slice() {
+            return stft.spectrum().to_vec();
+        }
+        let spectra: Vec<&[f32]> = self.stfts.iter().map(|stft| stft.spectrum()).collect();
+        blend_resolutions(&spectra, self.sample_rate)
+    }
+
+    /// Advance the monitors by one frame with the given spectrum, and return the smoothed bin levels.
+    pub fn smooth(&mut self, spectrum: &[f32]) -> Vec<f32> {
+        self.monitors
+            .iter_mut()
+            .enumerate()
+            .map(|(i, x)| {
+                x.tick(spectrum[i]);
+                x.level()
+            })
+            .collect()
+    }
+
+    pub fn bands(&self) -> &[OctaveBand] {
+        &self.bands
+    }
+
+    /// Sum the given spectrum into fractional-octave bands, advance the band monitors by one frame,
+    /// and return the smoothed band levels.
+    pub fn smooth_bands(&mut self, spectrum: &[f32]) -> Vec<f32> {
+        let enbw = self.stfts[0].enbw();
+        let levels = band_levels(spectrum, &self.bands, self.sample_rate, enbw);
+        self.band_monitors
+            .iter_mut()
+            .zip(levels)
+            .map(|(x, level)| {
+                x.tick(level);
+                x.level()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,125 @@
+//! Inter-channel coherence and phase, for checking mono compatibility.
+
+use num_complex::Complex32;
+
+use crate::editor::spectrum_analyzer::{
+    average::smoothing_factor, channel::front_pair, config::SpectrumAnalyzerConfig,
+    processing::position_to_frequency, stft::Stft,
+};
+
+// how many points each curve has
+const POINTS: usize = 1024;
+// the lowest mono loss that's reported
+const MIN_MONO_LOSS: f32 = -60.0; // db
+
+/// Time-averaged auto- and cross-spectra of the front left and right channels.
+pub struct CoherenceAnalyzer {
+    left: Stft,
+    right: Stft,
+    fft_size: usize,
+    // frames per second
+    frame_rate: f32,
+    // how much of each new frame gets mixed into the averages
+    alpha: f32,
+
+    left_power: Vec<f32>,
+    right_power: Vec<f32>,
+    cross: Vec<Complex32>,
+}
+
+/// The curves at evenly spaced positions on the frequency axis, from 0 to 1.
+pub struct CoherenceCurves {
+    // magnitude-squared coherence, from 0 to 1
+    pub coherence: Vec<(f32, f32)>,
+    // radians, from -pi to pi (positive when the left channel leads)
+    pub phase: Vec<(f32, f32)>,
+    // db, how much quieter the mid signal is than the channels themselves
+    pub mono_loss: Vec<(f32, f32)>,
+}
+
+impl CoherenceAnalyzer {
+    pub fn new(config: &SpectrumAnalyzerConfig, sample_rate: f32) -> Self {
+        let fft_size = config.fft_size;
+        let num_bins = fft_size / 2 + 1;
+        let frame_rate = sample_rate / config.overlap.hop(fft_size) as f32;
+
+        Self {
+            left: Stft::new(fft_size, config.window, config.overlap),
+            right: Stft::new(fft_size, config.window, config.overlap),
+            fft_size,
+            frame_rate,
+            alpha: smoothing_factor(frame_rate, config.coherence_time),
+            left_power: vec![0.0; num_bins],
+            right_power: vec![0.0; num_bins],
+            cross: vec![Complex32::new(0.0, 0.0); num_bins],
+        }
+    }
+
+    /// Set how long the spectra are averaged over, in seconds.
+    pub fn set_averaging_time(&mut self, seconds: f32) {
+        self.alpha = smoothing_factor(self.frame_rate, seconds);
+    }
+
+    pub fn push(&mut self, frame: &[f32]) {
+        let (left, right) = front_pair(frame);
+
+        let new_frame = self.left.push(left);
+        self.right.push(right);
+        if !new_frame {
+            return;
+        }
+
+        let alpha = self.alpha;
+        let bins = self.left.bins().iter().zip(self.right.bins());
+        for (k, (left, right)) in bins.enumerate() {
+            self.left_power[k] += alpha * (left.norm_sqr() - self.left_power[k]);
+            self.right_power[k] += alpha * (right.norm_sqr() - self.right_power[k]);
+            self.cross[k] += (left * right.conj() - self.cross[k]) * alpha;
+        }
+    }
+
+    pub fn curves(&self, sample_rate: f32, config: &SpectrumAnalyzerConfig) -> CoherenceCurves {
+        let last_bin = self.cross.len() - 1;
+        let mut curves = CoherenceCurves {
+            coherence: Vec::with_capacity(POINTS),
+            phase: Vec::with_capacity(POINTS),
+            mono_loss: Vec::with_capacity(POINTS),
+        };
+
+        for i in 0..POINTS {
+            let position = i as f32 / POINTS as f32;
+            let freq = position_to_frequency(position, config);
+            let w = (freq / sample_rate * self.fft_size as f32).clamp(0.0, last_bin as f32);
+            let index = w as usize;
+            let next = (index + 1).min(last_bin);
+            let fraction = w - index as f32;
+
+            // interpolating the cross-spectrum (instead of the phase) handles wrapping for free
+            let cross = self.cross[index] + (self.cross[next] - self.cross[index]) * fraction;
+            let lerp = |values: &[f32]| values[index] + (values[next] - values[index]) * fraction;
+            let left_power = lerp(&self.left_power);
+            let right_power = lerp(&self.right_power);
+
+            let total_power = left_power * right_power;
+            let coherence = if total_power > 0.0 {
+                (cross.norm_sqr() / total_power).min(1.0)
+            } else {
+                0.0
+            };
+
+            // |(L + R) / 2|^2, relative to the average power of L and R
+            let mid_power = (left_power + right_power + 2.0 * cross.re) / 4.0;
+            let mean_power = (left_power + right_power) / 2.0;
+            let mono_loss = if mean_power > 0.0 {
+                (10.0 * (mid_power.max(0.0) / mean_power).log10()).max(MIN_MONO_LOSS)
+            } else {
+                0.0
+            };
+
+            curves.coherence.push((position, coherence));
+            curves.phase.push((position, cross.arg()));
+            curves.mono_loss.push((position, mono_loss));
+        }
+        curves
+    }
+}
@@ -1,16 +1,88 @@
-use crate::editor::spectrum_analyzer::monitor::MonitorMode;
+use crate::editor::spectrum_analyzer::{
+    average::AverageMode,
+    bands::OctaveFraction,
+    channel::ChannelSource,
+    monitor::MonitorMode,
+    peak_hold::PeakFall,
+    processing::SlopeMode,
+    scale::FrequencyScale,
+    stft::Overlap,
+    units::{Calibration, MagnitudeUnit},
+    weighting::Weighting,
+    window::WindowFunction,
+};
 const DEFAULT_FREQ_RANGE: (f32, f32) = (20.0, 20_000.0); // hz
-const DEFAULT_MAGNITUDE_RANGE: (f32, f32) = (-100.0, 6.0); // db
-const DEFAULT_SLOPE: f32 = 4.5; // db/oct (or at least should be)
+const DEFAULT_FREQUENCY_SCALE: FrequencyScale = FrequencyScale::Log;
+const DEFAULT_MAGNITUDE_RANGE: (f32, f32) = (-100.0, 6.0); // dbfs
+const DEFAULT_UNIT: MagnitudeUnit = MagnitudeUnit::Dbfs;
+const DEFAULT_CALIBRATION: Calibration = Calibration {
+    // the EBU's alignment
+    dbu: 18.0,
+    // -20 dBFS pink noise at 85 dB SPL, as in film
+    spl: 105.0,
+};
+const DEFAULT_SLOPE: f32 = 4.5; // db/oct (in tilt mode)
+const DEFAULT_LEGACY_SLOPE: f32 = 4.5; // exponent (in legacy mode)
+const DEFAULT_SLOPE_MODE: SlopeMode = SlopeMode::Tilt;
+const DEFAULT_SLOPE_PIVOT: f32 = 1_000.0; // hz
 const DEFAULT_PEAK_DECAY: f32 = 0.25; // seconds
 const DEFAULT_INTERPOLATION: bool = true;
+const DEFAULT_CHANNEL_SOURCE: ChannelSource = ChannelSource::PerChannel;
+const DEFAULT_FFT_SIZE: usize = 4096;
+const DEFAULT_WINDOW: WindowFunction = WindowFunction::Hann;
+const DEFAULT_OVERLAP: Overlap = Overlap::ThreeQuarters;
+const DEFAULT_SIDECHAIN: bool = false;
+const DEFAULT_MULTI_RESOLUTION: bool = false;
+const DEFAULT_OCTAVE_FRACTION: OctaveFraction = OctaveFraction::Third;
+const DEFAULT_SPECTROGRAM_SPAN: f32 = 10.0; // seconds
+const DEFAULT_SPECTROGRAM_ROWS: usize = 256;
+const DEFAULT_PEAK_HOLD: bool = false;
+const DEFAULT_PEAK_HOLD_TIME: f32 = 1.0; // seconds
+const DEFAULT_PEAK_FALL: PeakFall = PeakFall::Linear(12.0); // db/s
+const DEFAULT_AVERAGE: bool = false;
+const DEFAULT_AVERAGE_MODE: AverageMode = AverageMode::Infinite;
+const DEFAULT_PEAK_COUNT: usize = 8;
+const DEFAULT_PITCH_TRACKER: bool = false;
+const DEFAULT_REFERENCE_PITCH: f32 = 440.0; // hz, A4
+const DEFAULT_CORRELATION_TIME: f32 = 0.3; // seconds
+const DEFAULT_COHERENCE: bool = false;
+const DEFAULT_COHERENCE_TIME: f32 = 1.0; // seconds
+const DEFAULT_WEIGHTING: Weighting = Weighting::Z;
 pub const DEFAULT_MONITOR_MODE: MonitorMode = MonitorMode::Rms(DEFAULT_PEAK_DECAY);
 
 pub struct SpectrumAnalyzerConfig {
     pub interpolate: bool,
     pub slope: f32,
+    pub slope_mode: SlopeMode,
+    pub slope_pivot: f32,
+    pub legacy_slope: f32,
     pub frequency_range: (f32, f32),
+    pub frequency_scale: FrequencyScale,
+    // in the current unit
     pub magnitude_range: (f32, f32),
+    pub unit: MagnitudeUnit,
+    pub calibration: Calibration,
+    pub channel_source: ChannelSource,
+    pub fft_size: usize,
+    pub window: WindowFunction,
+    pub overlap: Overlap,
+    pub sidechain: bool,
+    pub multi_resolution: bool,
+    pub octave_fraction: OctaveFraction,
+    pub spectrogram_span: f32,
+    pub spectrogram_rows: usize,
+    pub peak_hold: bool,
+    pub peak_hold_time: f32,
+    pub peak_fall: PeakFall,
+    pub average: bool,
+    pub average_mode: AverageMode,
+    pub peak_count: usize,
+    pub pitch_tracker: bool,
+    pub reference_pitch: f32,
+    pub correlation_time: f32,
+    pub coherence: bool,
+    pub coherence_time: f32,
+    pub weighting: Weighting,
 }
 
 impl Default for SpectrumAnalyzerConfig {
@@ -18,8 +90,35 @@ impl Default for SpectrumAnalyzerConfig {
         Self {
             interpolate: DEFAULT_INTERPOLATION,
             frequency_range: DEFAULT_FREQ_RANGE,
+            frequency_scale: DEFAULT_FREQUENCY_SCALE,
             magnitude_range: DEFAULT_MAGNITUDE_RANGE,
+            unit: DEFAULT_UNIT,
+            calibration: DEFAULT_CALIBRATION,
             slope: DEFAULT_SLOPE,
+            slope_mode: DEFAULT_SLOPE_MODE,
+            slope_pivot: DEFAULT_SLOPE_PIVOT,
+            legacy_slope: DEFAULT_LEGACY_SLOPE,
+            channel_source: DEFAULT_CHANNEL_SOURCE,
+            fft_size: DEFAULT_FFT_SIZE,
+            window: DEFAULT_WINDOW,
+            overlap: DEFAULT_OVERLAP,
+            sidechain: DEFAULT_SIDECHAIN,
+            multi_resolution: DEFAULT_MULTI_RESOLUTION,
+            octave_fraction: DEFAULT_OCTAVE_FRACTION,
+            spectrogram_span: DEFAULT_SPECTROGRAM_SPAN,
+            spectrogram_rows: DEFAULT_SPECTROGRAM_ROWS,
+            peak_hold: DEFAULT_PEAK_HOLD,
+            peak_hold_time: DEFAULT_PEAK_HOLD_TIME,
+            peak_fall: DEFAULT_PEAK_FALL,
+            average: DEFAULT_AVERAGE,
+            average_mode: DEFAULT_AVERAGE_MODE,
+            peak_count: DEFAULT_PEAK_COUNT,
+            pitch_tracker: DEFAULT_PITCH_TRACKER,
+            reference_pitch: DEFAULT_REFERENCE_PITCH,
+            correlation_time: DEFAULT_CORRELATION_TIME,
+            coherence: DEFAULT_COHERENCE,
+            coherence_time: DEFAULT_COHERENCE_TIME,
+            weighting: DEFAULT_WEIGHTING,
         }
     }
 }
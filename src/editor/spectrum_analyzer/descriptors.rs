@@ -0,0 +1,121 @@
+//! Spectral descriptors, which summarize the shape of a spectrum in a few numbers.
+
+// the share of the total power below the rolloff frequency
+const ROLLOFF: f64 = 0.85;
+
+// below this (mean power), the descriptors aren't meaningful
+const SILENCE: f64 = 1e-12;
+
+pub struct Descriptors {
+    // hz, the power-weighted mean frequency
+    pub centroid: f32,
+    // 0 (a single tone) to 1 (white noise)
+    pub flatness: f32,
+    // hz
+    pub rolloff: f32,
+    // db, the strongest bin above the mean
+    pub crest: f32,
+    // db/oct, the slope of a line fitted through the spectrum
+    pub tilt: f32,
+}
+
+/// The mean power of some (linear) spectra, bin by bin.
+pub fn mean_power<'a>(spectra: impl ExactSizeIterator<Item = &'a [f32]>) -> Vec<f32> {
+    let count = spectra.len() as f32;
+    let mut powers: Vec<f32> = Vec::new();
+    for spectrum in spectra {
+        powers.resize(spectrum.len(), 0.0);
+        for (power, level) in powers.iter_mut().zip(spectrum) {
+            *power += level * level / count;
+        }
+    }
+    powers
+}
+
+/// Describe a power spectrum, only looking at the bins within `frequency_range`.
+///
+/// Returns `None` for silence.
+pub fn describe(
+    powers: &[f32],
+    sample_rate: f32,
+    (min_freq, max_freq): (f32, f32),
+) -> Option<Descriptors> {
+    if powers.len() < 2 {
+        return None;
+    }
+    let fft_size = (powers.len() - 1) * 2;
+    let bin_width = sample_rate as f64 / fft_size as f64;
+    // (hz, power), never including DC
+    let bins: Vec<(f64, f64)> = powers
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(k, power)| (k as f64 * bin_width, *power as f64))
+        .filter(|(freq, _)| (min_freq as f64..=max_freq as f64).contains(freq))
+        .collect();
+    if bins.len() < 2 {
+        return None;
+    }
+
+    let count = bins.len() as f64;
+    let total: f64 = bins.iter().map(|(_, power)| power).sum();
+    let mean = total / count;
+    if mean < SILENCE {
+        return None;
+    }
+
+    let centroid = bins.iter().map(|(freq, power)| freq * power).sum::<f64>() / total;
+
+    let log_mean = bins
+        .iter()
+        .map(|(_, power)| power.max(SILENCE).ln())
+        .sum::<f64>()
+        / count;
+    let flatness = log_mean.exp() / mean;
+
+    let mut below = 0.0;
+    let rolloff = bins
+        .iter()
+        .find(|(_, power)| {
+            below += power;
+            below >= ROLLOFF * total
+        })
+        .map_or(bins[bins.len() - 1].0, |(freq, _)| *freq);
+
+    let max = bins.iter().map(|(_, power)| *power).fold(0.0, f64::max);
+    let crest = 10.0 * (max / mean).log10();
+
+    Some(Descriptors {
+        centroid: centroid as f32,
+        flatness: flatness as f32,
+        rolloff: rolloff as f32,
+        crest: crest as f32,
+        tilt: tilt(&bins) as f32,
+    })
+}
+
+/// The slope of a least-squares line through the spectrum in dB against octaves.
+///
+/// Bins are weighted by 1/f, so every octave counts the same (otherwise the top octave alone
+/// would have half the say).
+fn tilt(bins: &[(f64, f64)]) -> f64 {
+    let points = bins
+        .iter()
+        .map(|(freq, power)| (freq.log2(), 10.0 * power.max(SILENCE).log10(), 1.0 / freq));
+
+    let total_weight: f64 = points.clone().map(|(_, _, w)| w).sum();
+    let mean_x = points.clone().map(|(x, _, w)| w * x).sum::<f64>() / total_weight;
+    let mean_y = points.clone().map(|(_, y, w)| w * y).sum::<f64>() / total_weight;
+
+    let (covariance, variance) = points.fold((0.0, 0.0), |(covariance, variance), (x, y, w)| {
+        (
+            covariance + w * (x - mean_x) * (y - mean_y),
+            variance + w * (x - mean_x).powi(2),
+        )
+    });
+    if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    }
+}
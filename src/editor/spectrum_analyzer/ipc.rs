@@ -1,7 +1,19 @@
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-use crate::editor::spectrum_analyzer::monitor::MonitorMode;
+use crate::editor::spectrum_analyzer::{
+    average::AverageMode,
+    bands::OctaveFraction,
+    channel::ChannelSource,
+    monitor::MonitorMode,
+    peak_hold::PeakFall,
+    processing::SlopeMode,
+    scale::FrequencyScale,
+    stft::Overlap,
+    units::{Calibration, MagnitudeUnit},
+    weighting::Weighting,
+    window::WindowFunction,
+};
 
 #[derive(Deserialize, Serialize, TS, Debug)]
 #[serde(rename_all = "camelCase", tag = "type", content = "data")]
@@ -10,11 +22,58 @@ pub enum SpectrumAnalyzerConfigUpdate {
     Fps(f32),
     MonitorMode(MonitorMode),
     DecaySpeed(f32),
-    Interpolate(bool), /*
-                       TODO !! add these config options
-                       interpolate: bool,
-                       slope: f32,
-                       frequency_range: (f32, f32),
-                       magnitude_range: (f32, f32),
-                       */
+    Interpolate(bool),
+    ChannelSource(ChannelSource),
+    FftSize(usize),
+    Window(WindowFunction),
+    Overlap(Overlap),
+    Sidechain(bool),
+    MultiResolution(bool),
+    OctaveFraction(OctaveFraction),
+    SpectrogramSpan(f32),
+    SpectrogramRows(usize),
+    PeakHold(bool),
+    // seconds
+    PeakHoldTime(f32),
+    PeakFall(PeakFall),
+    Average(bool),
+    AverageMode(AverageMode),
+    // how many spectral peaks to find (0 turns peak detection off)
+    PeakCount(usize),
+    PitchTracker(bool),
+    // hz, the frequency of A4 that notes are named relative to
+    ReferencePitch(f32),
+    // seconds, how long the correlation meter integrates over
+    CorrelationTime(f32),
+    // per-frequency coherence and phase between the front left and right channels
+    Coherence(bool),
+    // seconds, how long the cross-spectra are averaged over
+    CoherenceTime(f32),
+    // applied to the spectrum (and references) on top of the slope
+    Weighting(Weighting),
+    // of the current mode: db/oct in tilt mode, or the (positive) exponent in legacy mode
+    Slope(f32),
+    SlopeMode(SlopeMode),
+    // hz, where the tilt crosses 0 db
+    SlopePivot(f32),
+    FrequencyScale(FrequencyScale),
+    // the magnitude range is converted along with it, so the view stays the same
+    MagnitudeUnit(MagnitudeUnit),
+    Calibration(Calibration), /*
+                              TODO !! add these config options
+                              interpolate: bool,
+                              frequency_range: (f32, f32),
+                              magnitude_range: (f32, f32),
+                              */
+}
+
+#[derive(Deserialize, Serialize, TS, Debug)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum SpectrumAnalyzerCommand {
+    ResetPeakHold,
+    ResetAverage,
+    // freeze the current spectrum into the slot with this name, replacing what was there
+    CaptureReference(String),
+    RemoveReference(String),
 }
@@ -1,30 +1,109 @@
+pub mod average;
+pub mod bands;
+mod bus;
+mod channel;
+mod coherence;
 mod config;
+mod descriptors;
 pub mod ipc;
 pub mod monitor;
+mod notes;
+pub mod peak_hold;
+mod peaks;
+mod pitch;
 mod processing;
+mod reference;
+pub mod scale;
+mod spectrogram;
+mod stereo;
+pub mod stft;
+pub mod units;
+pub mod weighting;
+pub mod window;
+use average::{Average, AverageMode};
+use bands::OctaveFraction;
+use bus::BusAnalyzer;
+use channel::ChannelSource;
+use coherence::CoherenceAnalyzer;
+use descriptors::{describe, mean_power, Descriptors};
 use monitor::Monitor;
+use notes::nearest_note;
+use peak_hold::{PeakFall, PeakHold};
+use peaks::find_peaks;
+use pitch::PitchTracker;
+use reference::{difference, Reference};
+use scale::FrequencyScale;
+use spectrogram::Spectrogram;
+use stereo::StereoMeter;
 
-use crossbeam_channel::Receiver;
-use fundsp::hacker32::*;
-use nih_plug::prelude::AtomicF32;
-use std::sync::{atomic::Ordering, Arc, Mutex};
+use nih_plug::{nih_warn, prelude::AtomicF32, util::gain_to_db};
+use std::{
+    collections::VecDeque,
+    sync::{atomic::Ordering, Arc},
+};
+use stft::Overlap;
+use units::{Calibration, MagnitudeUnit};
+use weighting::Weighting;
+use window::WindowFunction;
 
-use crate::editor::spectrum_analyzer::{
-    config::{SpectrumAnalyzerConfig, DEFAULT_MONITOR_MODE},
-    processing::{normalize, process_spectrum},
+use crate::{
+    capture::{Capture, CaptureHandle},
+    editor::{
+        ipc::{
+            BandLevel, BandTrace, CoherenceData, InputBus, PitchReading, ReferenceTrace,
+            SpectralDescriptors, SpectralPeak, SpectrogramData, SpectrumDisplay, SpectrumTrace,
+            TraceChannel, TraceLayer,
+        },
+        spectrum_analyzer::{
+            channel::frame_rate,
+            config::{SpectrumAnalyzerConfig, DEFAULT_MONITOR_MODE},
+            processing::{
+                frequency_grid, frequency_to_position, normalize, process_spectrum,
+                spectrogram_column, to_unit, SlopeMode,
+            },
+        },
+    },
 };
-const WINDOW_LENGTH: usize = 4096;
-const NUM_MONITORS: usize = (WINDOW_LENGTH / 2) + 1;
+const MIN_FFT_SIZE: usize = 512;
+const MAX_FFT_SIZE: usize = 32768;
+// how many spectral descriptor readings are kept (one per spectrum draw)
+const DESCRIPTOR_HISTORY: usize = 256;
+// bounds for the spectrogram history, which come straight from the GUI
+const MAX_SPECTROGRAM_SPAN: f32 = 60.0; // seconds
+const MAX_SPECTROGRAM_ROWS: usize = 1024;
+// small FFTs with a lot of overlap make thousands of columns per second
+const MAX_SPECTROGRAM_COLUMNS: usize = 1 << 16;
+// the longest averages, enough for a whole song
+const MAX_AVERAGE_TIME: f32 = 600.0; // seconds
+const MAX_AVERAGE_FRAMES: usize = 1 << 20;
 
 pub struct SpectrumAnalyzerHelper {
-    // NOTE: we could probably compute the FFT without fundsp
-    // (but i like fundsp)
-    graph: Box<dyn AudioUnit>,
+    main: BusAnalyzer,
+    // only analyzed when enabled
+    sidechain: Option<BusAnalyzer>,
+    spectrogram: Spectrogram,
+    // the main bus' latest raw (linear) spectrum, only searched for peaks when they're requested
+    latest_spectrum: Vec<f32>,
+    // only runs when enabled
+    pitch_tracker: Option<PitchTracker>,
+    stereo: StereoMeter,
+    // only runs when enabled
+    coherence: Option<CoherenceAnalyzer>,
+    // new monitors are cloned from this, so they pick up the current mode/fps/decay
+    monitor_template: Monitor,
 
-    sample_rx: Receiver<f32>,
+    capture_handle: CaptureHandle,
+    // the rings we're currently reading from
+    capture: Arc<Capture>,
+    overruns: usize,
+    // whether the last tick found new overruns, so each episode is only warned about once
+    falling_behind: bool,
 
-    spectrum: Arc<Mutex<Vec<f32>>>,
-    spectrum_monitors: Vec<Monitor>,
+    // the main bus' live (smoothed, linear) levels, as they were last drawn
+    last_live: Vec<(TraceChannel, Vec<f32>)>,
+    references: Vec<Reference>,
+    // of the main bus' live levels, oldest first
+    descriptors: VecDeque<Descriptors>,
 
     sample_rate: Arc<AtomicF32>,
 
@@ -32,98 +111,721 @@ pub struct SpectrumAnalyzerHelper {
 }
 
 impl SpectrumAnalyzerHelper {
-    pub fn new(sample_rate: Arc<AtomicF32>, sample_rx: Receiver<f32>) -> Self {
+    pub fn new(sample_rate: Arc<AtomicF32>, capture_handle: CaptureHandle) -> Self {
         let config = SpectrumAnalyzerConfig::default();
+        let monitor_template = Monitor::new(DEFAULT_MONITOR_MODE);
+
+        // whatever was captured while the editor was closed is stale
+        let capture = capture_handle.current();
+        capture.discard();
+
+        let rate = sample_rate.load(Ordering::Relaxed);
+        let main = BusAnalyzer::new(&config, capture.main.channels(), rate, &monitor_template);
+        let sidechain = config.sidechain.then(|| {
+            BusAnalyzer::new(
+                &config,
+                capture.sidechain.channels(),
+                rate,
+                &monitor_template,
+            )
+        });
 
-        let spectrum_monitors = vec![Monitor::new(DEFAULT_MONITOR_MODE); NUM_MONITORS];
-        let spectrum = Arc::new(Mutex::new(vec![0.0; NUM_MONITORS]));
+        let spectrogram = Spectrogram::new(spectrogram_capacity(&config, rate));
+        let pitch_tracker = config.pitch_tracker.then(|| PitchTracker::new(rate));
+        let stereo = StereoMeter::new(rate, config.correlation_time);
+        let coherence = config
+            .coherence
+            .then(|| CoherenceAnalyzer::new(&config, rate));
 
-        let mut graph = build_fft_graph(spectrum.clone());
-        graph.set_sample_rate(sample_rate.load(Ordering::Relaxed) as f64);
         Self {
-            spectrum,
-            spectrum_monitors,
-            graph,
+            main,
+            sidechain,
+            spectrogram,
+            latest_spectrum: Vec::new(),
+            pitch_tracker,
+            stereo,
+            coherence,
+            monitor_template,
             sample_rate,
-            sample_rx,
+            capture_handle,
+            capture,
+            overruns: 0,
+            falling_behind: false,
+            last_live: Vec::new(),
+            references: Vec::new(),
+            descriptors: VecDeque::with_capacity(DESCRIPTOR_HISTORY),
 
             config,
         }
     }
+    /// Start everything over, for a new sample rate or channel layout.
+    fn rebuild_all(&mut self) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.rebuild_buses();
+        self.rebuild_spectrogram();
+        self.pitch_tracker = self
+            .config
+            .pitch_tracker
+            .then(|| PitchTracker::new(sample_rate));
+        self.stereo = StereoMeter::new(sample_rate, self.config.correlation_time);
+        self.rebuild_coherence();
+    }
+    fn rebuild_buses(&mut self) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.main = BusAnalyzer::new(
+            &self.config,
+            self.capture.main.channels(),
+            sample_rate,
+            &self.monitor_template,
+        );
+        self.rebuild_sidechain();
+    }
+    fn rebuild_sidechain(&mut self) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.sidechain = self.config.sidechain.then(|| {
+            BusAnalyzer::new(
+                &self.config,
+                self.capture.sidechain.channels(),
+                sample_rate,
+                &self.monitor_template,
+            )
+        });
+    }
+    fn rebuild_coherence(&mut self) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.coherence = self
+            .config
+            .coherence
+            .then(|| CoherenceAnalyzer::new(&self.config, sample_rate));
+    }
     fn tick(&mut self) {
-        for sample in self.sample_rx.try_iter() {
-            self.graph.tick(&[sample], &mut [])
+        // the plugin makes a new ring when it's reinitialized
+        let current = self.capture_handle.current();
+        if !Arc::ptr_eq(&current, &self.capture) {
+            // the channel layout may have changed too
+            self.capture = current;
+            self.overruns = 0;
+            self.falling_behind = false;
+            self.rebuild_all();
         }
+
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let config = &self.config;
+        let main = &mut self.main;
+        let spectrogram = &mut self.spectrogram;
+        let latest_spectrum = &mut self.latest_spectrum;
+        let pitch_tracker = &mut self.pitch_tracker;
+        let stereo = &mut self.stereo;
+        let coherence = &mut self.coherence;
+        self.capture.main.pop(|frame| {
+            stereo.push(frame);
+            if let Some(coherence) = coherence {
+                coherence.push(frame);
+            }
+            if let Some(pitch_tracker) = pitch_tracker {
+                pitch_tracker.push(frame);
+            }
+            if main.tick(frame) {
+                let spectrum = main.raw_max();
+                let column =
+                    spectrogram_column(&spectrum, sample_rate, config.spectrogram_rows, config);
+                spectrogram.push(column);
+                *latest_spectrum = spectrum;
+            }
+        });
+
+        // the sidechain ring still has to be drained, even if we're not analyzing it
+        let sidechain = &mut self.sidechain;
+        self.capture.sidechain.pop(|frame| {
+            if let Some(sidechain) = sidechain {
+                sidechain.tick(frame);
+            }
+        });
+
+        let overruns = self.capture.overruns();
+        let falling_behind = overruns > self.overruns;
+        if falling_behind && !self.falling_behind {
+            nih_warn!("the analyzer fell behind and some audio was dropped");
+        }
+        self.falling_behind = falling_behind;
+        self.overruns = overruns;
     }
-    fn get_bin_levels(&mut self) -> Vec<f32> {
-        let spectrum = &*self.spectrum.lock().unwrap();
-        self.spectrum_monitors
-            .iter_mut()
-            .enumerate()
-            .map(|(i, x)| {
-                x.tick(spectrum[i]);
-                x.level()
-            })
-            .collect()
-    }
-    fn get_drawing_coordinates(&mut self) -> Vec<(f32, f32)> {
+    fn get_drawing_coordinates(&self, linear_levels: &[f32]) -> Vec<(f32, f32)> {
         let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let output = process_spectrum(linear_levels, sample_rate, &self.config);
+        self.get_processed_coordinates(&output)
+    }
+    fn get_processed_coordinates(&self, output: &[f32]) -> Vec<(f32, f32)> {
         let min_mag = self.config.magnitude_range.0;
         let max_mag = self.config.magnitude_range.1;
 
-        let linear_levels = self.get_bin_levels();
-        let output = process_spectrum(&linear_levels, sample_rate, &self.config);
         output
             .iter()
             .enumerate()
             .map(|(i, magnitude)| {
                 let freq_normalized = i as f32 / output.len() as f32;
 
-                let magnitude_normalized = normalize(*magnitude, min_mag, max_mag);
+                let magnitude = to_unit(*magnitude, &self.config);
+                let magnitude_normalized = normalize(magnitude, min_mag, max_mag);
 
                 (freq_normalized, magnitude_normalized)
             })
             .collect()
     }
 
+    fn monitors_mut(&mut self) -> impl Iterator<Item = &mut Monitor> {
+        std::iter::once(&mut self.monitor_template)
+            .chain(self.main.monitors_mut())
+            .chain(self.sidechain.iter_mut().flat_map(|bus| bus.monitors_mut()))
+    }
+
+    fn averages_mut(&mut self) -> impl Iterator<Item = &mut Average> {
+        self.main
+            .averages_mut()
+            .chain(self.sidechain.iter_mut().flat_map(|bus| bus.averages_mut()))
+    }
+
+    fn peak_holds_mut(&mut self) -> impl Iterator<Item = &mut PeakHold> {
+        self.main.peak_holds_mut().chain(
+            self.sidechain
+                .iter_mut()
+                .flat_map(|bus| bus.peak_holds_mut()),
+        )
+    }
+
+    pub fn set_channel_source(&mut self, source: ChannelSource) {
+        if source == self.config.channel_source {
+            return;
+        }
+        self.config.channel_source = source;
+        self.rebuild_buses();
+    }
+
+    /// Set the FFT size, which is rounded to a power of two between 512 and 32768.
+    pub fn set_fft_size(&mut self, size: usize) {
+        let size = size.clamp(MIN_FFT_SIZE, MAX_FFT_SIZE).next_power_of_two();
+        if size == self.config.fft_size {
+            return;
+        }
+        self.config.fft_size = size;
+        self.rebuild_buses();
+        self.rebuild_spectrogram();
+        self.rebuild_coherence();
+    }
+
+    pub fn set_window(&mut self, window: WindowFunction) {
+        if window == self.config.window {
+            return;
+        }
+        self.config.window = window;
+        self.rebuild_buses();
+        self.rebuild_coherence();
+    }
+
+    pub fn set_overlap(&mut self, overlap: Overlap) {
+        if overlap == self.config.overlap {
+            return;
+        }
+        self.config.overlap = overlap;
+        self.rebuild_buses();
+        self.rebuild_spectrogram();
+        self.rebuild_coherence();
+    }
+
+    pub fn set_sidechain(&mut self, enabled: bool) {
+        if enabled == self.config.sidechain {
+            return;
+        }
+        self.config.sidechain = enabled;
+        // the main bus doesn't depend on the sidechain, so it keeps its history
+        self.rebuild_sidechain();
+    }
+
+    pub fn set_multi_resolution(&mut self, enabled: bool) {
+        if enabled == self.config.multi_resolution {
+            return;
+        }
+        self.config.multi_resolution = enabled;
+        // the frame rate follows the smallest FFT
+        self.rebuild_buses();
+        self.rebuild_spectrogram();
+    }
+
+    pub fn set_octave_fraction(&mut self, fraction: OctaveFraction) {
+        if fraction == self.config.octave_fraction {
+            return;
+        }
+        self.config.octave_fraction = fraction;
+        self.rebuild_buses();
+    }
+
+    /// Set how much history the spectrogram keeps, up to a minute.
+    pub fn set_spectrogram_span(&mut self, seconds: f32) {
+        // (this also turns NaN into 0)
+        self.config.spectrogram_span = seconds.max(0.0).min(MAX_SPECTROGRAM_SPAN);
+        self.rebuild_spectrogram();
+    }
+
+    pub fn set_spectrogram_rows(&mut self, rows: usize) {
+        self.config.spectrogram_rows = rows.clamp(1, MAX_SPECTROGRAM_ROWS);
+        self.rebuild_spectrogram();
+    }
+
+    pub fn set_peak_hold(&mut self, enabled: bool) {
+        if enabled == self.config.peak_hold {
+            return;
+        }
+        self.config.peak_hold = enabled;
+        self.main.set_peak_hold(enabled);
+        if let Some(sidechain) = &mut self.sidechain {
+            sidechain.set_peak_hold(enabled);
+        }
+        // don't show peaks from before it was enabled
+        self.reset_peak_hold();
+    }
+
+    pub fn set_peak_hold_time(&mut self, seconds: f32) {
+        self.config.peak_hold_time = seconds.max(0.0);
+        let hold_time = self.config.peak_hold_time;
+        for peak_hold in self.peak_holds_mut() {
+            peak_hold.set_hold_time(hold_time);
+        }
+    }
+
+    pub fn set_peak_fall(&mut self, fall: PeakFall) {
+        // a negative rate would make the held peaks rise forever
+        let fall = match fall {
+            PeakFall::Linear(db_per_second) => PeakFall::Linear(db_per_second.max(0.0)),
+            PeakFall::Infinite => PeakFall::Infinite,
+        };
+        self.config.peak_fall = fall;
+        for peak_hold in self.peak_holds_mut() {
+            peak_hold.set_fall(fall);
+        }
+    }
+
+    pub fn reset_peak_hold(&mut self) {
+        for peak_hold in self.peak_holds_mut() {
+            peak_hold.reset();
+        }
+    }
+
+    pub fn set_average(&mut self, enabled: bool) {
+        if enabled == self.config.average {
+            return;
+        }
+        self.config.average = enabled;
+        self.rebuild_averages();
+    }
+
+    /// Set how the averages are taken, with times limited to 10 minutes.
+    pub fn set_average_mode(&mut self, mode: AverageMode) {
+        // (this also turns NaN into 0)
+        let clamp_time = |seconds: f32| seconds.max(0.0).min(MAX_AVERAGE_TIME);
+        let mode = match mode {
+            AverageMode::Infinite => AverageMode::Infinite,
+            AverageMode::Exponential(seconds) => AverageMode::Exponential(clamp_time(seconds)),
+            AverageMode::Window(seconds) => AverageMode::Window(clamp_time(seconds)),
+            AverageMode::Frames(length) => AverageMode::Frames(length.min(MAX_AVERAGE_FRAMES)),
+        };
+        if mode == self.config.average_mode {
+            return;
+        }
+        self.config.average_mode = mode;
+        self.rebuild_averages();
+    }
+
+    pub fn reset_average(&mut self) {
+        for average in self.averages_mut() {
+            average.reset();
+        }
+    }
+
+    /// Start every average over, without touching the rest of the analyzers.
+    fn rebuild_averages(&mut self) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let mode = self.config.average.then_some(self.config.average_mode);
+        let frame_rate = frame_rate(&self.config, sample_rate);
+        self.main.set_average(mode, frame_rate);
+        if let Some(sidechain) = &mut self.sidechain {
+            sidechain.set_average(mode, frame_rate);
+        }
+    }
+
+    /// Freeze the main bus' spectrum (as it was last drawn) into the slot with the given name.
+    pub fn capture_reference(&mut self, name: String) {
+        if self.last_live.is_empty() {
+            return;
+        }
+        let reference = Reference {
+            name,
+            sample_rate: self.sample_rate.load(Ordering::Relaxed),
+            traces: self.last_live.clone(),
+        };
+        match self
+            .references
+            .iter_mut()
+            .find(|r| r.name == reference.name)
+        {
+            Some(slot) => *slot = reference,
+            None => self.references.push(reference),
+        }
+    }
+
+    pub fn remove_reference(&mut self, name: &str) {
+        self.references.retain(|reference| reference.name != name);
+    }
+
+    pub fn set_pitch_tracker(&mut self, enabled: bool) {
+        if enabled == self.config.pitch_tracker {
+            return;
+        }
+        self.config.pitch_tracker = enabled;
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.pitch_tracker = enabled.then(|| PitchTracker::new(sample_rate));
+    }
+
+    pub fn set_reference_pitch(&mut self, frequency: f32) {
+        if frequency > 0.0 {
+            self.config.reference_pitch = frequency;
+        }
+    }
+
+    pub fn set_correlation_time(&mut self, seconds: f32) {
+        self.config.correlation_time = seconds;
+        self.stereo.set_integration_time(seconds);
+    }
+
+    pub fn set_coherence(&mut self, enabled: bool) {
+        if enabled == self.config.coherence {
+            return;
+        }
+        self.config.coherence = enabled;
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.coherence = enabled.then(|| CoherenceAnalyzer::new(&self.config, sample_rate));
+    }
+
+    pub fn set_coherence_time(&mut self, seconds: f32) {
+        self.config.coherence_time = seconds;
+        if let Some(coherence) = &mut self.coherence {
+            coherence.set_averaging_time(seconds);
+        }
+    }
+
+    /// Set the slope of the current mode. Each mode keeps its own, since the legacy exponent
+    /// has to be positive (the curve divides by it, and a negative one makes the gain negative).
+    pub fn set_slope(&mut self, slope: f32) {
+        match self.config.slope_mode {
+            SlopeMode::Tilt if slope.is_finite() => self.config.slope = slope,
+            SlopeMode::Legacy if slope > 0.0 && slope.is_finite() => {
+                self.config.legacy_slope = slope
+            }
+            _ => {}
+        }
+    }
+
+    pub fn set_slope_mode(&mut self, mode: SlopeMode) {
+        self.config.slope_mode = mode;
+    }
+
+    pub fn set_slope_pivot(&mut self, frequency: f32) {
+        if frequency > 0.0 {
+            self.config.slope_pivot = frequency;
+        }
+    }
+
+    pub fn set_frequency_scale(&mut self, scale: FrequencyScale) {
+        if scale == self.config.frequency_scale {
+            return;
+        }
+        self.config.frequency_scale = scale;
+        // the columns we have don't line up with the new axis
+        self.rebuild_spectrogram();
+    }
+
+    pub fn set_magnitude_unit(&mut self, unit: MagnitudeUnit) {
+        self.convert_magnitude_range(unit, self.config.calibration);
+    }
+
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.convert_magnitude_range(self.config.unit, calibration);
+    }
+
+    /// Switch units (or calibrations), keeping the magnitude range on the same levels.
+    fn convert_magnitude_range(&mut self, unit: MagnitudeUnit, calibration: Calibration) {
+        let (old_unit, old_calibration) = (self.config.unit, self.config.calibration);
+        let convert =
+            |value| unit.dbfs_to_unit(old_unit.unit_to_dbfs(value, &old_calibration), &calibration);
+        let (min_mag, max_mag) = self.config.magnitude_range;
+        self.config.magnitude_range = (convert(min_mag), convert(max_mag));
+        self.config.unit = unit;
+        self.config.calibration = calibration;
+    }
+
+    pub fn set_weighting(&mut self, weighting: Weighting) {
+        self.config.weighting = weighting;
+    }
+
+    pub fn set_peak_count(&mut self, count: usize) {
+        self.config.peak_count = count;
+    }
+
+    fn rebuild_spectrogram(&mut self) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        self.spectrogram = Spectrogram::new(spectrogram_capacity(&self.config, sample_rate));
+    }
+
     pub fn set_monitor_mode(&mut self, meter: monitor::MonitorMode) {
-        for mon in self.spectrum_monitors.iter_mut() {
+        for mon in self.monitors_mut() {
             mon.set_mode(meter);
         }
     }
 
     pub fn set_monitor_fps(&mut self, frame_rate: f32) {
-        for mon in self.spectrum_monitors.iter_mut() {
+        for mon in self.monitors_mut() {
             mon.set_frame_rate(frame_rate);
         }
     }
     pub fn set_monitor_decay_speed(&mut self, speed: f32) {
-        for mon in self.spectrum_monitors.iter_mut() {
+        for mon in self.monitors_mut() {
             mon.set_decay_speed(speed);
         }
     }
 
-    pub fn handle_draw_request(&mut self) -> Vec<(f32, f32)> {
+    pub fn handle_draw_request(&mut self) -> Vec<SpectrumTrace> {
         // QUESTION: is it cheaper to just always set the FPS, even if it hasn't changed?
         // (maybe the compiler will optimize the decay calculations or something)
         self.tick();
-        self.get_drawing_coordinates()
+
+        let mut traces: Vec<(InputBus, _)> = self
+            .main
+            .get_bin_levels()
+            .into_iter()
+            .map(|trace| (InputBus::Main, trace))
+            .collect();
+        if let Some(sidechain) = &mut self.sidechain {
+            traces.extend(
+                sidechain
+                    .get_bin_levels()
+                    .into_iter()
+                    .map(|trace| (InputBus::Sidechain, trace)),
+            );
+        }
+
+        self.last_live = traces
+            .iter()
+            .filter(|(bus, (_, layer, _))| *bus == InputBus::Main && *layer == TraceLayer::Live)
+            .map(|(_, (channel, _, linear_levels))| (*channel, linear_levels.clone()))
+            .collect();
+        self.push_descriptors();
+
+        traces
+            .into_iter()
+            .map(|(bus, (channel, layer, linear_levels))| SpectrumTrace {
+                bus,
+                channel,
+                layer,
+                points: self.get_drawing_coordinates(&linear_levels),
+            })
+            .collect()
     }
-}
 
-fn build_fft_graph(spectrum: Arc<Mutex<Vec<f32>>>) -> Box<dyn AudioUnit> {
-    let fft_processor = resynth::<U1, U0, _>(WINDOW_LENGTH, move |fft| {
-        let mut spectrum = spectrum.lock().unwrap();
+    fn push_descriptors(&mut self) {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let powers = mean_power(self.last_live.iter().map(|(_, levels)| levels.as_slice()));
+        // silence isn't recorded, so the history holds the last thing that was heard
+        if let Some(descriptors) = describe(&powers, sample_rate, self.config.frequency_range) {
+            if self.descriptors.len() == DESCRIPTOR_HISTORY {
+                self.descriptors.pop_front();
+            }
+            self.descriptors.push_back(descriptors);
+        }
+    }
+
+    pub fn handle_band_request(&mut self) -> Vec<BandTrace> {
+        self.tick();
+
+        let mut traces: Vec<(InputBus, _)> = self
+            .main
+            .get_band_levels()
+            .into_iter()
+            .map(|trace| (InputBus::Main, trace))
+            .collect();
+        if let Some(sidechain) = &mut self.sidechain {
+            traces.extend(
+                sidechain
+                    .get_band_levels()
+                    .into_iter()
+                    .map(|trace| (InputBus::Sidechain, trace)),
+            );
+        }
+
+        let (min_mag, max_mag) = self.config.magnitude_range;
+        // every analyzer uses the same bands
+        let bands = self.main.bands();
+        traces
+            .into_iter()
+            .map(|(bus, (channel, levels))| BandTrace {
+                bus,
+                channel,
+                // NOTE: unlike the spectrum, bands aren't sloped,
+                // since summing the bins already makes pink noise read flat
+                bands: bands
+                    .iter()
+                    .zip(levels)
+                    .map(|(band, level)| BandLevel {
+                        center: band.center,
+                        start: frequency_to_position(band.low, &self.config),
+                        end: frequency_to_position(band.high, &self.config),
+                        level: normalize(
+                            to_unit(gain_to_db(level), &self.config),
+                            min_mag,
+                            max_mag,
+                        ),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    pub fn handle_reference_request(&self) -> Vec<ReferenceTrace> {
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let live: Vec<(TraceChannel, Vec<f32>)> = self
+            .last_live
+            .iter()
+            .map(|(channel, levels)| {
+                (
+                    *channel,
+                    process_spectrum(levels, sample_rate, &self.config),
+                )
+            })
+            .collect();
+
+        self.references
+            .iter()
+            .flat_map(|reference| {
+                reference.traces.iter().map(|(channel, levels)| {
+                    let output = process_spectrum(levels, reference.sample_rate, &self.config);
+                    let difference = live
+                        .iter()
+                        .find(|(live_channel, _)| live_channel == channel)
+                        .map(|(_, live)| {
+                            let levels = difference(live, &output);
+                            levels
+                                .iter()
+                                .enumerate()
+                                .map(|(i, db)| (i as f32 / levels.len() as f32, *db))
+                                .collect()
+                        });
+                    ReferenceTrace {
+                        name: reference.name.clone(),
+                        channel: *channel,
+                        points: self.get_processed_coordinates(&output),
+                        difference,
+                    }
+                })
+            })
+            .collect()
+    }
+
+    pub fn handle_peak_request(&mut self) -> Vec<SpectralPeak> {
+        self.tick();
 
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..fft.bins() {
-            let current_bin = fft.at(0, i);
-            let normalization = WINDOW_LENGTH as f32;
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        find_peaks(
+            &self.latest_spectrum,
+            sample_rate,
+            self.config.peak_count,
+            self.config.frequency_range,
+        )
+        .iter()
+        .map(|peak| {
+            let (note, cents) = nearest_note(peak.frequency, self.config.reference_pitch);
+            SpectralPeak {
+                frequency: peak.frequency,
+                level: to_unit(peak.level, &self.config),
+                position: frequency_to_position(peak.frequency, &self.config),
+                note,
+                cents,
+            }
+        })
+        .collect()
+    }
 
-            let value = current_bin.norm() / normalization;
-            spectrum[i] = value;
+    pub fn handle_display_request(&self) -> SpectrumDisplay {
+        SpectrumDisplay {
+            frequency_scale: self.config.frequency_scale,
+            frequency_range: self.config.frequency_range,
+            grid: frequency_grid(&self.config),
+            weighting: self.config.weighting,
+            unit: self.config.unit,
+            full_scale: to_unit(0.0, &self.config),
+            magnitude_range: self.config.magnitude_range,
         }
-    });
+    }
+
+    pub fn handle_descriptor_request(&self) -> Vec<SpectralDescriptors> {
+        self.descriptors
+            .iter()
+            .map(|descriptors| SpectralDescriptors {
+                centroid: descriptors.centroid,
+                flatness: descriptors.flatness,
+                rolloff: descriptors.rolloff,
+                crest: descriptors.crest,
+                tilt: descriptors.tilt,
+            })
+            .collect()
+    }
+
+    pub fn handle_pitch_request(&mut self) -> Option<PitchReading> {
+        self.tick();
+
+        let estimate = self.pitch_tracker.as_ref()?.latest()?;
+        let (note, cents) = nearest_note(estimate.frequency, self.config.reference_pitch);
+        Some(PitchReading {
+            frequency: estimate.frequency,
+            note,
+            cents,
+            confidence: estimate.confidence,
+        })
+    }
+
+    pub fn handle_correlation_request(&mut self) -> Option<f32> {
+        self.tick();
+        self.stereo.correlation()
+    }
+
+    pub fn handle_goniometer_request(&mut self) -> Vec<(f32, f32)> {
+        self.tick();
+        self.stereo.take_points()
+    }
+
+    pub fn handle_coherence_request(&mut self) -> Option<CoherenceData> {
+        self.tick();
+
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        let curves = self.coherence.as_ref()?.curves(sample_rate, &self.config);
+        Some(CoherenceData {
+            coherence: curves.coherence,
+            phase: curves.phase,
+            mono_loss: curves.mono_loss,
+        })
+    }
+
+    pub fn handle_spectrogram_request(&mut self) -> SpectrogramData {
+        self.tick();
+
+        let sample_rate = self.sample_rate.load(Ordering::Relaxed);
+        SpectrogramData {
+            // a column is added for every frame
+            columns_per_second: frame_rate(&self.config, sample_rate),
+            rows: self.config.spectrogram_rows,
+            columns: self.spectrogram.take_new_columns(),
+        }
+    }
+}
 
-    Box::new(fft_processor)
+fn spectrogram_capacity(config: &SpectrumAnalyzerConfig, sample_rate: f32) -> usize {
+    let columns = (config.spectrogram_span * frame_rate(config, sample_rate)).ceil() as usize;
+    columns.min(MAX_SPECTROGRAM_COLUMNS)
 }
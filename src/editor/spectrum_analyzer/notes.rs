@@ -0,0 +1,18 @@
+const REFERENCE_NOTE: f32 = 69.0; // midi note number of A4
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// The nearest (equal-tempered) note to a frequency, like `"A4"`,
+/// and how many cents the frequency is above (or below) it.
+pub fn nearest_note(freq: f32, reference_pitch: f32) -> (String, f32) {
+    let note = REFERENCE_NOTE + 12.0 * (freq / reference_pitch).log2();
+    let nearest = note.round();
+    let cents = (note - nearest) * 100.0;
+
+    let nearest = nearest as i32;
+    let name = NOTE_NAMES[nearest.rem_euclid(12) as usize];
+    // midi note 0 is C-1
+    let octave = nearest.div_euclid(12) - 1;
+    (format!("{name}{octave}"), cents)
+}
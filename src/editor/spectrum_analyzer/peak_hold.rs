@@ -0,0 +1,75 @@
+//! Per-bin peak hold, drawn as a separate trace over the live spectrum.
+
+use nih_plug::util::db_to_gain;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+// what happens once a peak has been held for the hold time
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum PeakFall {
+    // peaks stay until they're reset
+    Infinite,
+    // db/s
+    Linear(f32),
+}
+
+pub struct PeakHold {
+    hold_time: f32,
+    fall: PeakFall,
+
+    // linear
+    levels: Vec<f32>,
+    // seconds since each peak was last raised
+    ages: Vec<f32>,
+}
+
+impl PeakHold {
+    pub fn new(num_bins: usize, hold_time: f32, fall: PeakFall) -> Self {
+        Self {
+            hold_time,
+            fall,
+            levels: vec![0.0; num_bins],
+            ages: vec![0.0; num_bins],
+        }
+    }
+
+    pub fn set_hold_time(&mut self, hold_time: f32) {
+        self.hold_time = hold_time;
+    }
+
+    pub fn set_fall(&mut self, fall: PeakFall) {
+        self.fall = fall;
+    }
+
+    pub fn reset(&mut self) {
+        self.levels.fill(0.0);
+        self.ages.fill(0.0);
+    }
+
+    /// Update the held peaks with new (linear) levels, `elapsed` seconds after the last update.
+    pub fn update(&mut self, levels: &[f32], elapsed: f32) {
+        let fall_factor = match self.fall {
+            PeakFall::Infinite => 1.0,
+            PeakFall::Linear(db_per_second) => db_to_gain(-db_per_second * elapsed),
+        };
+
+        for ((held, age), level) in self.levels.iter_mut().zip(&mut self.ages).zip(levels) {
+            if *level >= *held {
+                *held = *level;
+                *age = 0.0;
+                continue;
+            }
+            *age += elapsed;
+            if *age > self.hold_time {
+                *held = (*held * fall_factor).max(*level);
+            }
+        }
+    }
+
+    /// The held (linear) levels.
+    pub fn levels(&self) -> &[f32] {
+        &self.levels
+    }
+}
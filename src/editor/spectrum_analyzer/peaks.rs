@@ -0,0 +1,82 @@
+use nih_plug::util::gain_to_db;
+
+pub struct Peak {
+    // hz
+    pub frequency: f32,
+    // db
+    pub level: f32,
+}
+
+/// Find the `count` most prominent local maxima of a (linear) spectrum, most prominent first.
+///
+/// Only peaks within `frequency_range` are considered. Each peak's frequency and level are refined
+/// by fitting a parabola through the peak bin and its neighbours (in dB).
+pub fn find_peaks(
+    spectrum: &[f32],
+    sample_rate: f32,
+    count: usize,
+    (min_freq, max_freq): (f32, f32),
+) -> Vec<Peak> {
+    if count == 0 || spectrum.len() < 3 {
+        return Vec::new();
+    }
+    let fft_size = (spectrum.len() - 1) * 2;
+    let bin_width = sample_rate / fft_size as f32;
+    let db: Vec<f32> = spectrum.iter().map(|level| gain_to_db(*level)).collect();
+
+    let left = lowest_since_higher(db.iter().copied());
+    let mut right = lowest_since_higher(db.iter().rev().copied());
+    right.reverse();
+
+    let mut peaks: Vec<(f32, usize)> = (1..db.len() - 1)
+        .filter(|&k| db[k] > db[k - 1] && db[k] >= db[k + 1])
+        .filter(|&k| (min_freq..=max_freq).contains(&(k as f32 * bin_width)))
+        // how far the peak stands out above the lowest point between it and the next higher peak,
+        // on whichever side that point is higher
+        .map(|k| (db[k] - left[k].max(right[k]), k))
+        .collect();
+    peaks.sort_by(|a, b| b.0.total_cmp(&a.0));
+    peaks.truncate(count);
+
+    peaks
+        .into_iter()
+        .map(|(_, k)| {
+            let (left, center, right) = (db[k - 1], db[k], db[k + 1]);
+            let curvature = left - 2.0 * center + right;
+            // in bins, between -0.5 and 0.5
+            let offset = if curvature < 0.0 {
+                0.5 * (left - right) / curvature
+            } else {
+                0.0
+            };
+            Peak {
+                frequency: (k as f32 + offset) * bin_width,
+                level: center - 0.25 * (left - right) * offset,
+            }
+        })
+        .collect()
+}
+
+/// For each level, the lowest one between it and the closest higher level before it (or the
+/// start), including itself.
+///
+/// This keeps a stack of the levels that haven't been overtaken yet, so it's linear in the number
+/// of levels (scanning from every peak can be quadratic on falling spectra).
+fn lowest_since_higher(levels: impl Iterator<Item = f32>) -> Vec<f32> {
+    // (level, the lowest level between it and the one below it on the stack)
+    let mut stack: Vec<(f32, f32)> = Vec::new();
+    let mut lowest_levels = Vec::new();
+    for level in levels {
+        let mut lowest = f32::INFINITY;
+        while let Some(&(higher, between)) = stack.last() {
+            if higher > level {
+                break;
+            }
+            lowest = lowest.min(higher).min(between);
+            stack.pop();
+        }
+        stack.push((level, lowest));
+        lowest_levels.push(lowest.min(level));
+    }
+    lowest_levels
+}
@@ -0,0 +1,189 @@
+//! Monophonic pitch detection, using the YIN algorithm.
+//!
+//! See "YIN, a fundamental frequency estimator for speech and music" (de Cheveigné & Kawahara, 2002).
+
+use nih_plug::util::db_to_gain;
+use std::f32::consts::{FRAC_1_SQRT_2, TAU};
+
+// the range of fundamentals we look for
+const MIN_FREQUENCY: f32 = 30.0; // hz
+const MAX_FREQUENCY: f32 = 2_000.0; // hz
+
+// how often a new estimate is made
+const DETECTION_RATE: f32 = 25.0; // hz
+
+// the input is decimated to roughly this rate, so the (quadratic) difference function
+// costs the same at any sample rate
+const ANALYSIS_RATE: f32 = 24_000.0; // hz
+
+// the anti-aliasing filter's cutoff, relative to the decimated rate
+const ANTI_ALIASING_CUTOFF: f32 = 0.4;
+
+// dips in the normalized difference function below this count as periods
+const THRESHOLD: f32 = 0.15;
+
+// anything quieter than this is treated as silence
+const SILENCE: f32 = -60.0; // db
+
+pub struct PitchEstimate {
+    // hz
+    pub frequency: f32,
+    // 0 to 1
+    pub confidence: f32,
+}
+
+/// A second-order Butterworth low-pass (transposed direct form II).
+struct LowPass {
+    b: [f32; 3],
+    a: [f32; 2],
+    state: [f32; 2],
+}
+
+impl LowPass {
+    fn new(cutoff: f32, sample_rate: f32) -> Self {
+        let w = TAU * cutoff / sample_rate;
+        let alpha = w.sin() / (2.0 * FRAC_1_SQRT_2);
+        let a0 = 1.0 + alpha;
+        let b1 = (1.0 - w.cos()) / a0;
+        Self {
+            b: [b1 / 2.0, b1, b1 / 2.0],
+            a: [-2.0 * w.cos() / a0, (1.0 - alpha) / a0],
+            state: [0.0; 2],
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+pub struct PitchTracker {
+    // of the decimated signal
+    sample_rate: f32,
+    // only every `decimation`th (filtered) sample is analyzed
+    decimation: usize,
+    until_next_sample: usize,
+    anti_aliasing: [LowPass; 2],
+
+    // the longest period we look for, which is also the length of the integration window
+    max_lag: usize,
+    min_lag: usize,
+
+    // circular, holds two windows' worth of samples
+    history: Vec<f32>,
+    position: usize,
+    hop: usize,
+    until_next: usize,
+
+    latest: Option<PitchEstimate>,
+}
+
+impl PitchTracker {
+    pub fn new(input_rate: f32) -> Self {
+        let decimation = ((input_rate / ANALYSIS_RATE) as usize).max(1);
+        let sample_rate = input_rate / decimation as f32;
+        let cutoff = ANTI_ALIASING_CUTOFF * sample_rate;
+
+        let max_lag = (sample_rate / MIN_FREQUENCY).ceil().max(2.0) as usize;
+        let min_lag = ((sample_rate / MAX_FREQUENCY).floor() as usize).max(1);
+        let hop = ((sample_rate / DETECTION_RATE) as usize).max(1);
+
+        Self {
+            sample_rate,
+            decimation,
+            until_next_sample: decimation,
+            anti_aliasing: [
+                LowPass::new(cutoff, input_rate),
+                LowPass::new(cutoff, input_rate),
+            ],
+            max_lag,
+            min_lag,
+            history: vec![0.0; max_lag * 2],
+            position: 0,
+            hop,
+            until_next: hop,
+            latest: None,
+        }
+    }
+
+    /// Push a frame of interleaved samples, which are mixed down to mono.
+    pub fn push(&mut self, frame: &[f32]) {
+        let mut sample = frame.iter().sum::<f32>() / frame.len().max(1) as f32;
+        if self.decimation > 1 {
+            for filter in &mut self.anti_aliasing {
+                sample = filter.process(sample);
+            }
+            self.until_next_sample -= 1;
+            if self.until_next_sample > 0 {
+                return;
+            }
+            self.until_next_sample = self.decimation;
+        }
+
+        self.history[self.position] = sample;
+        self.position = (self.position + 1) % self.history.len();
+
+        self.until_next -= 1;
+        if self.until_next == 0 {
+            self.until_next = self.hop;
+            self.latest = self.detect();
+        }
+    }
+
+    /// The latest estimate, or `None` if there's no clear pitch.
+    pub fn latest(&self) -> Option<&PitchEstimate> {
+        self.latest.as_ref()
+    }
+
+    fn detect(&self) -> Option<PitchEstimate> {
+        // oldest first
+        let (newer, older) = self.history.split_at(self.position);
+        let signal: Vec<f32> = older.iter().chain(newer).copied().collect();
+
+        let window = self.max_lag;
+        let power = signal[..window].iter().map(|x| x * x).sum::<f32>() / window as f32;
+        if power < db_to_gain(SILENCE).powi(2) {
+            return None;
+        }
+
+        // the cumulative mean normalized difference function
+        let mut normalized = vec![1.0; self.max_lag + 1];
+        let mut running_sum = 0.0;
+        for lag in 1..=self.max_lag {
+            let difference: f32 = signal[..window]
+                .iter()
+                .zip(&signal[lag..])
+                .map(|(a, b)| (a - b).powi(2))
+                .sum();
+            running_sum += difference;
+            normalized[lag] = if running_sum > 0.0 {
+                difference * lag as f32 / running_sum
+            } else {
+                1.0
+            };
+        }
+
+        // the first dip below the threshold, followed down to its minimum
+        let mut lag = (self.min_lag..self.max_lag).find(|&lag| normalized[lag] < THRESHOLD)?;
+        while lag + 1 < self.max_lag && normalized[lag + 1] < normalized[lag] {
+            lag += 1;
+        }
+
+        // refine the period with a parabola through the neighbouring lags
+        let (left, center, right) = (normalized[lag - 1], normalized[lag], normalized[lag + 1]);
+        let curvature = left - 2.0 * center + right;
+        let offset = if curvature > 0.0 {
+            0.5 * (left - right) / curvature
+        } else {
+            0.0
+        };
+
+        Some(PitchEstimate {
+            frequency: self.sample_rate / (lag as f32 + offset),
+            confidence: (1.0 - center).clamp(0.0, 1.0),
+        })
+    }
+}
@@ -1,7 +1,29 @@
-use nih_plug::util::{gain_to_db, gain_to_db_fast};
+use nih_plug::util::{db_to_gain, gain_to_db, gain_to_db_fast};
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
+use ts_rs::TS;
 
-use crate::editor::spectrum_analyzer::{config::SpectrumAnalyzerConfig, WINDOW_LENGTH};
+use crate::editor::spectrum_analyzer::config::SpectrumAnalyzerConfig;
+
+// grid lines go at these multiples of every power of ten
+const GRID_MULTIPLES: [f32; 3] = [1.0, 2.0, 5.0];
+
+// crossovers between the bass/mid/high FFTs in multi-resolution mode
+const MULTI_RESOLUTION_CROSSOVERS: [f32; 2] = [250.0, 2_000.0]; // hz
+const CROSSOVER_WIDTH: f32 = 1.0; // octaves
+
+// how many points an interpolated spectrum has, whatever the FFT size
+const INTERPOLATED_LENGTH: usize = 4096;
+
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum SlopeMode {
+    // exactly `slope` db per octave, crossing 0 db at the pivot frequency
+    Tilt,
+    // the original curve, which isn't a constant db/oct
+    Legacy,
+}
 
 // https://gist.github.com/ollpu/231ebbf3717afec50fb09108aea6ad2f
 // TODO: optimize this function
@@ -13,25 +35,25 @@ pub fn process_spectrum(
 ) -> Vec<f32> {
     // TODO: make radius configurable?
     let radius = 10;
-    let slope = config.slope;
-    let min_freq = config.frequency_range.0;
-    let max_freq = config.frequency_range.1;
-    // NOTE: is WINDOW_LENGTH a correct length for the interpolated output?
+    // the input holds (fft_size / 2) + 1 bins
+    let fft_size = (input.len() - 1) * 2;
+    let last_bin = input.len() - 1;
+    // the display resolution doesn't grow with the FFT size,
+    // so large FFTs don't flood the GUI with points
     let length = if config.interpolate {
-        WINDOW_LENGTH
+        INTERPOLATED_LENGTH
     } else {
         input.len()
     };
     let mut output = vec![0.0; length];
+    let weighting = config.weighting.curve(sample_rate);
 
     for (index, res) in output.iter_mut().enumerate() {
         // i in [0, N[
         // x normalized to [0, 1[
         let normalized_freq = index as f32 / length as f32;
-        // We want to map x to frequency in range [min, max[, log scale
-        // Parameters k, b. f = k*b^x
-        let b = max_freq / min_freq;
-        let current_freq_log = min_freq * b.powf(normalized_freq);
+        // map x to a frequency in [min, max[, on whichever scale the axis uses
+        let current_freq = position_to_frequency(normalized_freq, config);
 
         // NOTE:
         // if we skip interpolation, we get a blocky look
@@ -39,10 +61,12 @@ pub fn process_spectrum(
         // (or at least, i think it's caused by that)
 
         // Closest FFT bin
-        let w = current_freq_log / sample_rate * length as f32;
-        let p = (w as isize).clamp(0, (length / 2) as isize);
+        let w = current_freq / sample_rate * fft_size as f32;
+        let p = (w as isize).clamp(0, last_bin as isize);
 
-        let slope_factor_linear = calculate_slope_factor(current_freq_log, slope, sample_rate);
+        // the weighting curve is applied together with the slope
+        let slope_factor_linear =
+            slope_factor(current_freq, sample_rate, config) * weighting.gain(current_freq);
 
         if !config.interpolate {
             // TODO: possibly refactor to reduce repitition
@@ -57,7 +81,7 @@ pub fn process_spectrum(
         // (expensive)
         let mut result = 0.;
         for iw in p - radius..=p + radius + 1 {
-            if iw < 0 || iw > (length / 2) as isize {
+            if iw < 0 || iw > last_bin as isize {
                 continue;
             }
             let delta = w - iw as f32;
@@ -82,6 +106,63 @@ pub fn process_spectrum(
     }
     output
 }
+/// Stitch spectra of different FFT sizes (largest first) onto the bins of the largest one.
+///
+/// The largest FFT covers the lows, and each smaller one takes over above the next crossover,
+/// with a crossfade so the result is continuous.
+///
+/// Every spectrum reads a full-scale sine as 1.0, and that's kept as is, since peaks and level
+/// readouts are taken from the result. The tradeoff is that a wider bin also picks up more noise,
+/// so broadband levels step up by 6 dB at every crossover (each FFT is 4x smaller).
+pub fn blend_resolutions(spectra: &[&[f32]], sample_rate: f32) -> Vec<f32> {
+    let num_bins = spectra[0].len();
+    let largest_fft_size = (num_bins - 1) * 2;
+
+    (0..num_bins)
+        .map(|bin| {
+            let freq = bin as f32 * sample_rate / largest_fft_size as f32;
+
+            let mut result = 0.0;
+            // how much of this bin hasn't been taken by a lower band yet
+            let mut remaining = 1.0;
+            for (band, spectrum) in spectra.iter().enumerate() {
+                let weight = match MULTI_RESOLUTION_CROSSOVERS.get(band) {
+                    Some(crossover) if band < spectra.len() - 1 => {
+                        let octaves = (freq / crossover).log2();
+                        let upper = (octaves / CROSSOVER_WIDTH + 0.5).clamp(0.0, 1.0);
+                        remaining * (1.0 - upper)
+                    }
+                    _ => remaining,
+                };
+                remaining -= weight;
+                if weight > 0.0 {
+                    result += weight * sample_linear(spectrum, bin, num_bins);
+                }
+            }
+            result
+        })
+        .collect()
+}
+
+/// Sample a spectrum at the frequency of `bin` on a grid with `num_bins` bins.
+fn sample_linear(spectrum: &[f32], bin: usize, num_bins: usize) -> f32 {
+    let position = bin as f32 * (spectrum.len() - 1) as f32 / (num_bins - 1) as f32;
+    let index = position as usize;
+    let fraction = position - index as f32;
+
+    let current = spectrum[index];
+    let next = spectrum.get(index + 1).copied().unwrap_or(current);
+    current + (next - current) * fraction
+}
+
+/// The (linear) gain the slope applies at a frequency.
+fn slope_factor(freq: f32, sample_rate: f32, config: &SpectrumAnalyzerConfig) -> f32 {
+    match config.slope_mode {
+        SlopeMode::Tilt => db_to_gain(config.slope * (freq / config.slope_pivot).log2()),
+        SlopeMode::Legacy => calculate_slope_factor(freq, config.legacy_slope, sample_rate),
+    }
+}
+
 fn calculate_slope_factor(freq: f32, slope: f32, sample_rate: f32) -> f32 {
     let half_nyquist = sample_rate / 2.0;
 
@@ -89,6 +170,81 @@ fn calculate_slope_factor(freq: f32, slope: f32, sample_rate: f32) -> f32 {
     (freq + 1.).log2().powf(slope) / magnitude_slope_divisor
 }
 
+/// Resample a spectrum onto `rows` frequencies spaced like the axis, as normalized, sloped dB values.
+///
+/// This is a much cheaper version of `process_spectrum` (linear interpolation instead of Lanczos),
+/// since it runs for every spectrogram column.
+pub fn spectrogram_column(
+    input: &[f32],
+    sample_rate: f32,
+    rows: usize,
+    config: &SpectrumAnalyzerConfig,
+) -> Vec<f32> {
+    let (min_mag, max_mag) = config.magnitude_range;
+    let fft_size = (input.len() - 1) * 2;
+    let last_bin = input.len() - 1;
+    let weighting = config.weighting.curve(sample_rate);
+
+    (0..rows)
+        .map(|row| {
+            let normalized_freq = row as f32 / rows as f32;
+            let freq = position_to_frequency(normalized_freq, config);
+
+            let w = (freq / sample_rate * fft_size as f32).clamp(0.0, last_bin as f32);
+            let index = w as usize;
+            let next = (index + 1).min(last_bin);
+            let level = input[index] + (input[next] - input[index]) * (w - index as f32);
+
+            let slope_factor_linear =
+                slope_factor(freq, sample_rate, config) * weighting.gain(freq);
+            let db = gain_to_db_fast(level * slope_factor_linear);
+            normalize(to_unit(db, config), min_mag, max_mag).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// The frequency at a position on the frequency axis, from 0 to 1.
+pub fn position_to_frequency(position: f32, config: &SpectrumAnalyzerConfig) -> f32 {
+    let scale = config.frequency_scale;
+    let (min, max) = (
+        scale.warp(config.frequency_range.0),
+        scale.warp(config.frequency_range.1),
+    );
+    scale.unwarp(min + (max - min) * position)
+}
+
+/// Where a frequency lies on the frequency axis, from 0 to 1.
+pub fn frequency_to_position(freq: f32, config: &SpectrumAnalyzerConfig) -> f32 {
+    let scale = config.frequency_scale;
+    let (min, max) = (
+        scale.warp(config.frequency_range.0),
+        scale.warp(config.frequency_range.1),
+    );
+    normalize(scale.warp(freq), min, max)
+}
+
+/// Round frequencies within the frequency range, with their positions on the axis, for grid lines.
+pub fn frequency_grid(config: &SpectrumAnalyzerConfig) -> Vec<(f32, f32)> {
+    let (min_freq, max_freq) = config.frequency_range;
+    let mut grid = Vec::new();
+    let mut decade = 10f32.powf(min_freq.max(1.0).log10().floor());
+    while decade <= max_freq {
+        for multiple in GRID_MULTIPLES {
+            let freq = decade * multiple;
+            if (min_freq..=max_freq).contains(&freq) {
+                grid.push((freq, frequency_to_position(freq, config)));
+            }
+        }
+        decade *= 10.0;
+    }
+    grid
+}
+
+/// Convert a level in dBFS into the configured (calibrated) unit.
+pub fn to_unit(dbfs: f32, config: &SpectrumAnalyzerConfig) -> f32 {
+    config.unit.dbfs_to_unit(dbfs, &config.calibration)
+}
+
 pub fn normalize(value: f32, min: f32, max: f32) -> f32 {
     (value - min) / (max - min)
 }
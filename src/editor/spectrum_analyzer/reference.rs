@@ -0,0 +1,27 @@
+use crate::editor::ipc::TraceChannel;
+
+/// A frozen copy of the main bus' spectrum, to compare the live one against.
+///
+/// The levels are kept as (smoothed, linear) bins rather than as drawn points, so the reference
+/// follows any later changes to the display settings.
+pub struct Reference {
+    pub name: String,
+    pub sample_rate: f32,
+    pub traces: Vec<(TraceChannel, Vec<f32>)>,
+}
+
+/// Subtract two processed (dB) spectra, resampling the reference onto the live one's points.
+pub fn difference(live: &[f32], reference: &[f32]) -> Vec<f32> {
+    live.iter()
+        .enumerate()
+        .map(|(i, live)| {
+            // both cover the same frequency range, but may have a different number of points
+            let position = i as f32 * reference.len() as f32 / live.len() as f32;
+            let index = (position as usize).min(reference.len() - 1);
+            let next = (index + 1).min(reference.len() - 1);
+            let fraction = position - index as f32;
+            let reference = reference[index] + (reference[next] - reference[index]) * fraction;
+            live - reference
+        })
+        .collect()
+}
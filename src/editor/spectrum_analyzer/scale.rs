@@ -0,0 +1,42 @@
+//! Frequency axis scales.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum FrequencyScale {
+    Linear,
+    Log,
+    // 2595 * log10(1 + f / 700)
+    Mel,
+    // Traunmüller's approximation: 26.81 * f / (1960 + f) - 0.53
+    Bark,
+    // Glasberg & Moore's ERB-rate: 21.4 * log10(1 + 0.00437 * f)
+    Erb,
+}
+
+impl FrequencyScale {
+    /// Map a frequency (in hz) onto the scale.
+    pub fn warp(self, freq: f32) -> f32 {
+        match self {
+            FrequencyScale::Linear => freq,
+            FrequencyScale::Log => freq.ln(),
+            FrequencyScale::Mel => 2595.0 * (1.0 + freq / 700.0).log10(),
+            FrequencyScale::Bark => 26.81 * freq / (1960.0 + freq) - 0.53,
+            FrequencyScale::Erb => 21.4 * (1.0 + 0.00437 * freq).log10(),
+        }
+    }
+
+    /// The inverse of `warp`.
+    pub fn unwarp(self, value: f32) -> f32 {
+        match self {
+            FrequencyScale::Linear => value,
+            FrequencyScale::Log => value.exp(),
+            FrequencyScale::Mel => 700.0 * (10f32.powf(value / 2595.0) - 1.0),
+            FrequencyScale::Bark => 1960.0 * (value + 0.53) / (26.28 - value),
+            FrequencyScale::Erb => (10f32.powf(value / 21.4) - 1.0) / 0.00437,
+        }
+    }
+}
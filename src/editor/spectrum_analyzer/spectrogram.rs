@@ -0,0 +1,35 @@
+use std::collections::VecDeque;
+
+/// A rolling history of spectrum frames, for the scrolling spectrogram view.
+pub struct Spectrogram {
+    capacity: usize,
+    columns: VecDeque<Vec<f32>>,
+    // how many of the newest columns haven't been sent to the GUI yet
+    unsent: usize,
+}
+
+impl Spectrogram {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            columns: VecDeque::with_capacity(capacity),
+            unsent: 0,
+        }
+    }
+
+    pub fn push(&mut self, column: Vec<f32>) {
+        if self.columns.len() == self.capacity {
+            self.columns.pop_front();
+        }
+        self.columns.push_back(column);
+        self.unsent = (self.unsent + 1).min(self.capacity);
+    }
+
+    /// Every column pushed since the last call, oldest first.
+    pub fn take_new_columns(&mut self) -> Vec<Vec<f32>> {
+        let skip = self.columns.len() - self.unsent;
+        self.unsent = 0;
+        self.columns.iter().skip(skip).cloned().collect()
+    }
+}
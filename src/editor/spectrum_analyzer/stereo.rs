@@ -0,0 +1,79 @@
+//! Stereo image: phase correlation and goniometer points, from the front left/right channels.
+
+use std::collections::VecDeque;
+
+use crate::editor::spectrum_analyzer::{average::smoothing_factor, channel::front_pair};
+
+// roughly how many goniometer points are kept every second
+const GONIOMETER_RATE: f32 = 8_000.0; // hz
+
+// the most points kept between requests, the oldest are dropped first
+const MAX_GONIOMETER_POINTS: usize = 4096;
+
+// below this (mean power), the correlation isn't meaningful
+const SILENCE: f32 = 1e-10;
+
+pub struct StereoMeter {
+    sample_rate: f32,
+    // how much of each new sample gets mixed into the averages
+    alpha: f32,
+    // time-averaged products of left and right
+    left_right: f32,
+    left_left: f32,
+    right_right: f32,
+
+    decimation: usize,
+    until_next_point: usize,
+    // (mid, side)
+    points: VecDeque<(f32, f32)>,
+}
+
+impl StereoMeter {
+    /// `integration_time` is the correlation's time constant, in seconds.
+    pub fn new(sample_rate: f32, integration_time: f32) -> Self {
+        let decimation = ((sample_rate / GONIOMETER_RATE).round() as usize).max(1);
+        Self {
+            sample_rate,
+            alpha: smoothing_factor(sample_rate, integration_time),
+            left_right: 0.0,
+            left_left: 0.0,
+            right_right: 0.0,
+            decimation,
+            until_next_point: decimation,
+            points: VecDeque::with_capacity(MAX_GONIOMETER_POINTS),
+        }
+    }
+
+    pub fn set_integration_time(&mut self, integration_time: f32) {
+        self.alpha = smoothing_factor(self.sample_rate, integration_time);
+    }
+
+    pub fn push(&mut self, frame: &[f32]) {
+        let (left, right) = front_pair(frame);
+
+        self.left_right += self.alpha * (left * right - self.left_right);
+        self.left_left += self.alpha * (left * left - self.left_left);
+        self.right_right += self.alpha * (right * right - self.right_right);
+
+        self.until_next_point -= 1;
+        if self.until_next_point == 0 {
+            self.until_next_point = self.decimation;
+            if self.points.len() == MAX_GONIOMETER_POINTS {
+                self.points.pop_front();
+            }
+            self.points
+                .push_back(((left + right) * 0.5, (left - right) * 0.5));
+        }
+    }
+
+    /// From -1 (out of phase) to 1 (mono), or `None` for silence.
+    pub fn correlation(&self) -> Option<f32> {
+        let power = (self.left_left * self.right_right).sqrt();
+        (power > SILENCE).then(|| (self.left_right / power).clamp(-1.0, 1.0))
+    }
+
+    /// Every (mid, side) point since the last call, oldest first.
+    pub fn take_points(&mut self) -> Vec<(f32, f32)> {
+        self.points.drain(..).collect()
+    }
+}
@@ -0,0 +1,134 @@
+//! A small analysis-only STFT.
+//!
+//! `resynth` from fundsp always applies its own window, so we do the windowing here
+//! and only use fundsp for the FFT itself.
+
+use fundsp::fft::real_fft;
+use num_complex::Complex32;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::editor::spectrum_analyzer::window::{
+    coherent_gain, equivalent_noise_bandwidth, WindowFunction,
+};
+
+// how much consecutive frames overlap
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum Overlap {
+    // 50%
+    Half,
+    // 75%
+    ThreeQuarters,
+    // 87.5%
+    SevenEighths,
+    // 93.75%
+    FifteenSixteenths,
+}
+
+impl Overlap {
+    /// The number of samples between consecutive frames.
+    pub fn hop(self, fft_size: usize) -> usize {
+        let divisor = match self {
+            Overlap::Half => 2,
+            Overlap::ThreeQuarters => 4,
+            Overlap::SevenEighths => 8,
+            Overlap::FifteenSixteenths => 16,
+        };
+        fft_size / divisor
+    }
+}
+
+pub struct Stft {
+    fft_size: usize,
+    hop: usize,
+
+    window: Vec<f32>,
+    // maps FFT magnitudes back to (peak) sine amplitudes
+    amplitude_correction: f32,
+    enbw: f32,
+
+    // the last `fft_size` input samples, used as a circular buffer
+    history: Vec<f32>,
+    write_index: usize,
+    samples_until_frame: usize,
+
+    scratch: Vec<f32>,
+    bins: Vec<Complex32>,
+    spectrum: Vec<f32>,
+}
+
+impl Stft {
+    pub fn new(fft_size: usize, window: WindowFunction, overlap: Overlap) -> Self {
+        let window = window.build(fft_size);
+        let amplitude_correction = 2.0 / (coherent_gain(&window) * fft_size as f32);
+        let enbw = equivalent_noise_bandwidth(&window);
+        let num_bins = fft_size / 2 + 1;
+        let hop = overlap.hop(fft_size);
+
+        Self {
+            fft_size,
+            hop,
+            window,
+            amplitude_correction,
+            enbw,
+            history: vec![0.0; fft_size],
+            write_index: 0,
+            samples_until_frame: hop,
+            scratch: vec![0.0; fft_size],
+            bins: vec![Complex32::new(0.0, 0.0); num_bins],
+            spectrum: vec![0.0; num_bins],
+        }
+    }
+
+    /// Push a single sample. Returns `true` if a new spectrum frame was computed.
+    pub fn push(&mut self, sample: f32) -> bool {
+        self.history[self.write_index] = sample;
+        self.write_index = (self.write_index + 1) % self.fft_size;
+
+        self.samples_until_frame -= 1;
+        if self.samples_until_frame > 0 {
+            return false;
+        }
+        self.samples_until_frame = self.hop;
+        self.process_frame();
+        true
+    }
+
+    /// The latest linear magnitudes, where a full-scale sine reads 1.0 regardless of window.
+    pub fn spectrum(&self) -> &[f32] {
+        &self.spectrum
+    }
+
+    /// The latest (windowed, unscaled) complex bins.
+    pub fn bins(&self) -> &[Complex32] {
+        &self.bins
+    }
+
+    /// The window's equivalent noise bandwidth, in bins.
+    pub fn enbw(&self) -> f32 {
+        self.enbw
+    }
+
+    fn process_frame(&mut self) {
+        // unroll the circular buffer (oldest sample first) and apply the window
+        for (i, value) in self.scratch.iter_mut().enumerate() {
+            let sample = self.history[(self.write_index + i) % self.fft_size];
+            *value = sample * self.window[i];
+        }
+
+        real_fft(&mut self.scratch, &mut self.bins);
+
+        let last_bin = self.bins.len() - 1;
+        for (i, (level, bin)) in self.spectrum.iter_mut().zip(&self.bins).enumerate() {
+            // DC and nyquist don't have a mirrored negative-frequency bin
+            let correction = if i == 0 || i == last_bin {
+                self.amplitude_correction / 2.0
+            } else {
+                self.amplitude_correction
+            };
+            *level = bin.norm() * correction;
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Calibrated magnitude units, so readouts can be in real-world levels.
+
+use nih_plug::util::{db_to_gain, gain_to_db};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+// 0 dBV (1 V RMS) in dBu (0.775 V RMS)
+const DBV_IN_DBU: f32 = 2.2185;
+
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum MagnitudeUnit {
+    Dbfs,
+    Dbu,
+    Dbv,
+    DbSpl,
+    // where full scale is 1
+    Linear,
+}
+
+/// What full scale corresponds to in the real world. A full-scale sine reads 0 dBFS.
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+#[ts(export)]
+pub struct Calibration {
+    // dbu, the level of a full-scale sine at the converters (dBV follows from this)
+    pub dbu: f32,
+    // db spl, the level a full-scale sine would be played back at (or was recorded from)
+    pub spl: f32,
+}
+
+impl MagnitudeUnit {
+    /// Convert a level from dBFS into this unit.
+    pub fn dbfs_to_unit(self, dbfs: f32, calibration: &Calibration) -> f32 {
+        match self {
+            MagnitudeUnit::Linear => db_to_gain(dbfs),
+            _ => dbfs + self.offset(calibration),
+        }
+    }
+
+    /// Convert a level in this unit back into dBFS.
+    pub fn unit_to_dbfs(self, value: f32, calibration: &Calibration) -> f32 {
+        match self {
+            MagnitudeUnit::Linear => gain_to_db(value),
+            _ => value - self.offset(calibration),
+        }
+    }
+
+    /// What 0 dBFS reads in this unit, for the decibel units.
+    fn offset(self, calibration: &Calibration) -> f32 {
+        match self {
+            MagnitudeUnit::Dbfs | MagnitudeUnit::Linear => 0.0,
+            MagnitudeUnit::Dbu => calibration.dbu,
+            MagnitudeUnit::Dbv => calibration.dbu - DBV_IN_DBU,
+            MagnitudeUnit::DbSpl => calibration.spl,
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! Standard frequency weighting curves, for viewing the spectrum the way a meter would hear it.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::meters::loudness::KWeightingResponse;
+
+// the poles shared by the A, B and C curves (IEC 61672-1)
+const POLE_LOW: f64 = 20.598997; // hz
+const POLE_HIGH: f64 = 12194.217; // hz
+const POLE_A_1: f64 = 107.65265; // hz
+const POLE_A_2: f64 = 737.86223; // hz
+const POLE_B: f64 = 158.48932; // hz
+
+// the gains that normalize each curve to 0 dB at 1 kHz
+const GAIN_A: f64 = 2.0; // db
+const GAIN_B: f64 = 0.17; // db
+const GAIN_C: f64 = 0.06; // db
+const GAIN_468: f64 = 18.2; // db
+
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum Weighting {
+    // none
+    Z,
+    A,
+    B,
+    C,
+    // the BS.1770 pre-filter that loudness is measured through
+    K,
+    // ITU-R 468, for noise
+    Itu468,
+}
+
+impl Weighting {
+    /// The curve at a sample rate (which only matters for K), to evaluate at many frequencies.
+    pub fn curve(self, sample_rate: f32) -> WeightingCurve {
+        WeightingCurve {
+            weighting: self,
+            k_weighting: KWeightingResponse::new(sample_rate),
+        }
+    }
+}
+
+pub struct WeightingCurve {
+    weighting: Weighting,
+    // derived once, rather than for every frequency
+    k_weighting: KWeightingResponse,
+}
+
+impl WeightingCurve {
+    /// The (linear) gain of the curve at a frequency.
+    pub fn gain(&self, freq: f32) -> f32 {
+        let f = freq as f64;
+        let f2 = f * f;
+        let db_to_gain = |db: f64| 10f64.powf(db / 20.0);
+
+        let gain = match self.weighting {
+            Weighting::Z => 1.0,
+            Weighting::A => {
+                POLE_HIGH.powi(2) * f2 * f2
+                    / ((f2 + POLE_LOW.powi(2))
+                        * ((f2 + POLE_A_1.powi(2)) * (f2 + POLE_A_2.powi(2))).sqrt()
+                        * (f2 + POLE_HIGH.powi(2)))
+                    * db_to_gain(GAIN_A)
+            }
+            Weighting::B => {
+                POLE_HIGH.powi(2) * f2 * f
+                    / ((f2 + POLE_LOW.powi(2))
+                        * (f2 + POLE_B.powi(2)).sqrt()
+                        * (f2 + POLE_HIGH.powi(2)))
+                    * db_to_gain(GAIN_B)
+            }
+            Weighting::C => {
+                POLE_HIGH.powi(2) * f2 / ((f2 + POLE_LOW.powi(2)) * (f2 + POLE_HIGH.powi(2)))
+                    * db_to_gain(GAIN_C)
+            }
+            Weighting::K => self.k_weighting.gain(freq) as f64,
+            Weighting::Itu468 => {
+                let h1 = -4.737_338_981_378_384e-24 * f2.powi(3)
+                    + 2.043_828_333_606_125e-15 * f2 * f2
+                    - 1.363_894_795_463_638e-7 * f2
+                    + 1.0;
+                let h2 = 1.306_612_257_412_824e-19 * f2 * f2 * f
+                    - 2.118_150_887_518_656e-11 * f2 * f
+                    + 5.559_488_023_498_642e-4 * f;
+                1.246_332_637_532_143e-4 * f / h1.hypot(h2) * db_to_gain(GAIN_468)
+            }
+        };
+        gain as f32
+    }
+}
@@ -0,0 +1,88 @@
+//! Window functions for the STFT.
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+use ts_rs::TS;
+
+#[derive(Deserialize, Serialize, TS, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+#[ts(export)]
+pub enum WindowFunction {
+    Hann,
+    Hamming,
+    BlackmanHarris,
+    FlatTop,
+    // beta
+    Kaiser(f32),
+}
+
+impl WindowFunction {
+    /// Build a periodic (DFT-even) window of the given length.
+    pub fn build(self, length: usize) -> Vec<f32> {
+        (0..length)
+            .map(|n| {
+                let x = n as f32 / length as f32;
+                match self {
+                    WindowFunction::Hann => cosine_sum(x, &[0.5, 0.5]),
+                    WindowFunction::Hamming => cosine_sum(x, &[0.54, 0.46]),
+                    WindowFunction::BlackmanHarris => {
+                        cosine_sum(x, &[0.35875, 0.48829, 0.14128, 0.01168])
+                    }
+                    WindowFunction::FlatTop => cosine_sum(
+                        x,
+                        &[0.21557894, 0.41663158, 0.27726316, 0.083578944, 0.006947368],
+                    ),
+                    WindowFunction::Kaiser(beta) => {
+                        let t = 2.0 * x - 1.0;
+                        bessel_i0(beta * (1.0 - t * t).max(0.0).sqrt()) / bessel_i0(beta)
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// The mean of the window, i.e. how much a windowed sine is attenuated.
+///
+/// Dividing by this (and the FFT length) brings a sine back to its true amplitude.
+pub fn coherent_gain(window: &[f32]) -> f32 {
+    window.iter().sum::<f32>() / window.len() as f32
+}
+
+/// Equivalent noise bandwidth, in bins.
+///
+/// Broadband signals are spread over this many bins, so summing bin powers overestimates
+/// their level by this factor.
+pub fn equivalent_noise_bandwidth(window: &[f32]) -> f32 {
+    let sum: f32 = window.iter().sum();
+    let sum_of_squares: f32 = window.iter().map(|w| w * w).sum();
+    window.len() as f32 * sum_of_squares / (sum * sum)
+}
+
+/// Generalized cosine window: a0 - a1 cos(2πx) + a2 cos(4πx) - ...
+fn cosine_sum(x: f32, coefficients: &[f32]) -> f32 {
+    coefficients
+        .iter()
+        .enumerate()
+        .map(|(k, a)| {
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            sign * a * (TAU * k as f32 * x).cos()
+        })
+        .sum()
+}
+
+/// Zeroth-order modified Bessel function of the first kind (power series).
+fn bessel_i0(x: f32) -> f32 {
+    let half_x = x / 2.0;
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for k in 1..50 {
+        term *= half_x / k as f32;
+        let squared = term * term;
+        sum += squared;
+        if squared < sum * 1e-9 {
+            break;
+        }
+    }
+    sum
+}
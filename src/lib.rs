@@ -1,36 +1,59 @@
+mod capture;
 mod dsp;
 mod editor;
+mod meters;
 mod params;
 
-use crossbeam_channel::{bounded, Receiver, Sender};
+use capture::{Capture, CaptureHandle};
 use fundsp::hacker32::*;
+use meters::{
+    loudness::{LoudnessMeter, LoudnessReadings},
+    true_peak::{PeakReadings, TruePeakMeter},
+};
 use nih_plug::prelude::*;
 use params::PluginParams;
 use std::sync::{atomic::Ordering, Arc};
 
 use crate::{dsp::build_graph, editor::PluginGui};
 
+const DEFAULT_SAMPLE_RATE: f32 = 44100.0;
+
 // TODO: need to persist spectrum analyzer configuration
 struct SpectrumAnalyzerPlugin {
     params: Arc<PluginParams>,
     graph: BigBlockAdapter,
     buffers: Vec<Vec<f32>>,
 
-    sample_tx: Sender<f32>,
-    sample_rx: Receiver<f32>,
+    // the audio thread writes into this one directly,
+    capture: Arc<Capture>,
+    // and the editor picks it up through here
+    capture_handle: CaptureHandle,
+
+    loudness: LoudnessMeter,
+    // the readings are shared with the editor, and the meters keep measuring across
+    // reinitializations, unless the channel layout or sample rate changes
+    loudness_readings: Arc<LoudnessReadings>,
+    true_peak: TruePeakMeter,
+    peak_readings: Arc<PeakReadings>,
 
     sample_rate: Arc<AtomicF32>,
 }
 
 impl Default for SpectrumAnalyzerPlugin {
     fn default() -> Self {
-        let (tx, rx) = bounded(1024);
+        let capture = Arc::new(Capture::new(2, 2, DEFAULT_SAMPLE_RATE));
+        let loudness_readings = Arc::new(LoudnessReadings::default());
+        let peak_readings = Arc::new(PeakReadings::default());
         Self {
             params: Arc::new(PluginParams::default()),
             graph: BigBlockAdapter::new(Box::new(sink())),
             buffers: Vec::new(),
-            sample_tx: tx,
-            sample_rx: rx,
+            capture_handle: CaptureHandle::new(capture.clone()),
+            capture,
+            loudness: LoudnessMeter::new(2, DEFAULT_SAMPLE_RATE, loudness_readings.clone()),
+            loudness_readings,
+            true_peak: TruePeakMeter::new(2, peak_readings.clone()),
+            peak_readings,
             sample_rate: Arc::new(AtomicF32::new(0.0)),
         }
     }
@@ -44,14 +67,69 @@ impl Plugin for SpectrumAnalyzerPlugin {
 
     const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
-    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[AudioIOLayout {
-        main_input_channels: NonZeroU32::new(2),
-        main_output_channels: NonZeroU32::new(2),
+    const AUDIO_IO_LAYOUTS: &'static [AudioIOLayout] = &[
+        // the first layout is the default
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(2),
+            main_output_channels: NonZeroU32::new(2),
+
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[],
+            names: PortNames {
+                layout: Some("Stereo"),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(1),
+            main_output_channels: NonZeroU32::new(1),
+
+            aux_input_ports: &[new_nonzero_u32(1)],
+            aux_output_ports: &[],
+            names: PortNames {
+                layout: Some("Mono"),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(6),
+            main_output_channels: NonZeroU32::new(6),
 
-        aux_input_ports: &[],
-        aux_output_ports: &[],
-        names: PortNames::const_default(),
-    }];
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[],
+            names: PortNames {
+                layout: Some("5.1"),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(8),
+            main_output_channels: NonZeroU32::new(8),
+
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[],
+            names: PortNames {
+                layout: Some("7.1"),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+        },
+        AudioIOLayout {
+            main_input_channels: NonZeroU32::new(12),
+            main_output_channels: NonZeroU32::new(12),
+
+            aux_input_ports: &[new_nonzero_u32(2)],
+            aux_output_ports: &[],
+            names: PortNames {
+                layout: Some("7.1.4"),
+                aux_inputs: &["Sidechain"],
+                ..PortNames::const_default()
+            },
+        },
+    ];
 
     const MIDI_INPUT: MidiConfig = MidiConfig::None;
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
@@ -68,15 +146,46 @@ impl Plugin for SpectrumAnalyzerPlugin {
 
     fn initialize(
         &mut self,
-        _audio_io_layout: &AudioIOLayout,
+        audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
         _context: &mut impl InitContext<Self>,
     ) -> bool {
-        self.buffers = vec![vec![0.0; buffer_config.max_buffer_size as usize]; 2];
+        let num_channels = audio_io_layout
+            .main_input_channels
+            .map_or(0, NonZeroU32::get) as usize;
+        let num_sidechain_channels = audio_io_layout
+            .aux_input_ports
+            .first()
+            .map_or(0, NonZeroU32::get) as usize;
+
+        self.buffers = vec![vec![0.0; buffer_config.max_buffer_size as usize]; num_channels];
         self.sample_rate
             .store(buffer_config.sample_rate, Ordering::Relaxed);
 
-        let graph = build_graph(self.sample_tx.clone());
+        self.capture = Arc::new(Capture::new(
+            num_channels,
+            num_sidechain_channels,
+            buffer_config.sample_rate,
+        ));
+        self.capture_handle.replace(self.capture.clone());
+
+        // hosts reactivate plugins for all sorts of reasons (like a new buffer size),
+        // which shouldn't throw away the integrated loudness or the held peaks
+        if !self
+            .loudness
+            .matches(num_channels, buffer_config.sample_rate)
+        {
+            self.loudness = LoudnessMeter::new(
+                num_channels,
+                buffer_config.sample_rate,
+                self.loudness_readings.clone(),
+            );
+        }
+        if !self.true_peak.matches(num_channels) {
+            self.true_peak = TruePeakMeter::new(num_channels, self.peak_readings.clone());
+        }
+
+        let graph = build_graph(num_channels);
 
         self.graph = BigBlockAdapter::new(graph);
         self.graph
@@ -94,21 +203,35 @@ impl Plugin for SpectrumAnalyzerPlugin {
     fn editor(&mut self, _: AsyncExecutor<Self>) -> Option<Box<dyn Editor>> {
         PluginGui::new_editor(
             &self.params.state,
-            self.sample_rx.clone(),
+            self.capture_handle.clone(),
             self.sample_rate.clone(),
+            self.loudness_readings.clone(),
+            self.peak_readings.clone(),
         )
     }
 
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
+        aux: &mut AuxiliaryBuffers,
         _context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
         for (i, chan) in buffer.as_slice_immutable().iter().enumerate() {
             self.buffers[i][..buffer.samples()].copy_from_slice(chan);
         }
 
+        self.capture
+            .main
+            .push_block(&self.buffers, buffer.samples());
+        if let Some(sidechain) = aux.inputs.first() {
+            self.capture
+                .sidechain
+                .push_block(sidechain.as_slice_immutable(), buffer.samples());
+        }
+
+        self.loudness.process(&self.buffers, buffer.samples());
+        self.true_peak.process(&self.buffers, buffer.samples());
+
         self.graph
             .process_big(buffer.samples(), &self.buffers, buffer.as_slice());
 
@@ -124,7 +247,9 @@ impl ClapPlugin for SpectrumAnalyzerPlugin {
 
     const CLAP_FEATURES: &'static [ClapFeature] = &[
         ClapFeature::AudioEffect,
+        ClapFeature::Mono,
         ClapFeature::Stereo,
+        ClapFeature::Surround,
         ClapFeature::Analyzer,
         ClapFeature::Mastering,
         ClapFeature::Mixing,
@@ -137,7 +262,9 @@ impl Vst3Plugin for SpectrumAnalyzerPlugin {
 
     const VST3_SUBCATEGORIES: &'static [Vst3SubCategory] = &[
         Vst3SubCategory::Tools,
+        Vst3SubCategory::Mono,
         Vst3SubCategory::Stereo,
+        Vst3SubCategory::Surround,
         Vst3SubCategory::Fx,
         Vst3SubCategory::Mastering,
     ];
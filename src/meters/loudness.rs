@@ -0,0 +1,510 @@
+//! Loudness metering, following ITU-R BS.1770-4 and EBU R128 (with EBU Tech 3342 for the range).
+
+use nih_plug::prelude::AtomicF32;
+use std::{
+    f64::consts::PI,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+// measurements are made every 100 ms, over the last 400 ms (momentary) or 3 s (short-term)
+const STEP_SECONDS: f32 = 0.1;
+const MOMENTARY_STEPS: usize = 4;
+const SHORT_TERM_STEPS: usize = 30;
+
+const ABSOLUTE_GATE: f64 = -70.0; // lufs
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0; // lu
+const RANGE_RELATIVE_GATE: f64 = -20.0; // lu
+
+// the percentiles of short-term loudness the range lies between
+const RANGE_PERCENTILES: (f64, f64) = (0.10, 0.95);
+
+// gated loudness is accumulated in histograms, so it never needs more memory
+const HISTOGRAM_MAX: f64 = 10.0; // lufs
+const HISTOGRAM_RESOLUTION: f64 = 0.1; // lu
+const HISTOGRAM_BINS: usize = ((HISTOGRAM_MAX - ABSOLUTE_GATE) / HISTOGRAM_RESOLUTION) as usize;
+
+/// The latest readings, shared with the editor. Everything is in LUFS (or LU for the range), and
+/// negative infinity until there's enough (loud enough) audio to measure.
+pub struct LoudnessReadings {
+    pub momentary: AtomicF32,
+    pub short_term: AtomicF32,
+    pub integrated: AtomicF32,
+    pub range: AtomicF32,
+
+    reset: AtomicBool,
+}
+
+impl Default for LoudnessReadings {
+    fn default() -> Self {
+        Self {
+            momentary: AtomicF32::new(f32::NEG_INFINITY),
+            short_term: AtomicF32::new(f32::NEG_INFINITY),
+            integrated: AtomicF32::new(f32::NEG_INFINITY),
+            range: AtomicF32::new(f32::NEG_INFINITY),
+            reset: AtomicBool::new(false),
+        }
+    }
+}
+
+impl LoudnessReadings {
+    /// Ask the audio thread to start measuring from scratch.
+    pub fn request_reset(&self) {
+        self.reset.store(true, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        for reading in [
+            &self.momentary,
+            &self.short_term,
+            &self.integrated,
+            &self.range,
+        ] {
+            reading.store(f32::NEG_INFINITY, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A second-order IIR filter (transposed direct form II).
+#[derive(Clone)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.state[0];
+        self.state[0] = self.b[1] * x - self.a[0] * y + self.state[1];
+        self.state[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+
+    /// The magnitude of the frequency response at `freq`.
+    fn magnitude(&self, freq: f64, sample_rate: f64) -> f64 {
+        let w = 2.0 * PI * freq / sample_rate;
+        let (b, a) = (self.b, self.a);
+        let numerator_re = b[0] + b[1] * w.cos() + b[2] * (2.0 * w).cos();
+        let numerator_im = -(b[1] * w.sin() + b[2] * (2.0 * w).sin());
+        let denominator_re = 1.0 + a[0] * w.cos() + a[1] * (2.0 * w).cos();
+        let denominator_im = -(a[0] * w.sin() + a[1] * (2.0 * w).sin());
+        numerator_re.hypot(numerator_im) / denominator_re.hypot(denominator_im)
+    }
+}
+
+/// The K-weighting pre-filter (a high shelf followed by a high-pass), for any sample rate.
+///
+/// The coefficients in BS.1770 are only given for 48 kHz, so they're derived from the analog
+/// prototype here (the same way libebur128 does it).
+fn k_weighting(sample_rate: f32) -> [Biquad; 2] {
+    let sample_rate = sample_rate as f64;
+
+    let shelf = {
+        let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        }
+    };
+    let high_pass = {
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        Biquad {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            state: [0.0; 2],
+        }
+    };
+
+    [shelf, high_pass]
+}
+
+/// The frequency response of the K-weighting pre-filter at one sample rate.
+pub struct KWeightingResponse {
+    filters: [Biquad; 2],
+    sample_rate: f64,
+}
+
+impl KWeightingResponse {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            filters: k_weighting(sample_rate),
+            sample_rate: sample_rate as f64,
+        }
+    }
+
+    /// The (linear) gain at a frequency.
+    pub fn gain(&self, freq: f32) -> f32 {
+        self.filters
+            .iter()
+            .map(|filter| filter.magnitude(freq as f64, self.sample_rate))
+            .product::<f64>() as f32
+    }
+}
+
+/// How much each channel contributes to the total loudness.
+fn channel_weight(channel: usize, num_channels: usize) -> f64 {
+    match (num_channels, channel) {
+        (1 | 2, _) => 1.0,
+        // the LFE isn't measured
+        (_, 3) => 0.0,
+        // surrounds are about 1.5 dB louder (heights aren't)
+        (_, 4..=7) => 1.41,
+        _ => 1.0,
+    }
+}
+
+fn power_to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+/// Counts (and total powers) of gated measurements, in 0.1 LU steps above the absolute gate.
+struct Histogram {
+    counts: Box<[u64]>,
+    powers: Box<[f64]>,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; HISTOGRAM_BINS].into_boxed_slice(),
+            powers: vec![0.0; HISTOGRAM_BINS].into_boxed_slice(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.fill(0);
+        self.powers.fill(0.0);
+    }
+
+    fn bin(lufs: f64) -> usize {
+        (((lufs - ABSOLUTE_GATE) / HISTOGRAM_RESOLUTION) as usize).min(HISTOGRAM_BINS - 1)
+    }
+
+    fn bin_loudness(bin: usize) -> f64 {
+        ABSOLUTE_GATE + (bin as f64 + 0.5) * HISTOGRAM_RESOLUTION
+    }
+
+    /// Add a measurement, unless it's below the absolute gate.
+    fn add(&mut self, power: f64) {
+        let lufs = power_to_lufs(power);
+        if lufs < ABSOLUTE_GATE {
+            return;
+        }
+        let bin = Self::bin(lufs);
+        self.counts[bin] += 1;
+        self.powers[bin] += power;
+    }
+
+    /// The first bin at or above a gate that's relative to the mean of everything measured.
+    fn relative_gate(&self, relative_gate: f64) -> Option<usize> {
+        let count: u64 = self.counts.iter().sum();
+        if count == 0 {
+            return None;
+        }
+        let mean = self.powers.iter().sum::<f64>() / count as f64;
+        let gate = power_to_lufs(mean) + relative_gate;
+        Some(if gate < ABSOLUTE_GATE {
+            0
+        } else {
+            Self::bin(gate)
+        })
+    }
+
+    fn integrated(&self) -> Option<f64> {
+        let gate = self.relative_gate(INTEGRATED_RELATIVE_GATE)?;
+        let count: u64 = self.counts[gate..].iter().sum();
+        let power: f64 = self.powers[gate..].iter().sum();
+        (count > 0).then(|| power_to_lufs(power / count as f64))
+    }
+
+    fn range(&self) -> Option<f64> {
+        let gate = self.relative_gate(RANGE_RELATIVE_GATE)?;
+        let count: u64 = self.counts[gate..].iter().sum();
+        if count == 0 {
+            return None;
+        }
+
+        let percentile = |fraction: f64| {
+            let target = (fraction * (count - 1) as f64).round() as u64;
+            let mut seen = 0;
+            for (bin, bin_count) in self.counts.iter().enumerate().skip(gate) {
+                seen += bin_count;
+                if seen > target {
+                    return Self::bin_loudness(bin);
+                }
+            }
+            Self::bin_loudness(HISTOGRAM_BINS - 1)
+        };
+        let (low, high) = RANGE_PERCENTILES;
+        Some(percentile(high) - percentile(low))
+    }
+}
+
+/// Measures the loudness of a bus. This runs on the audio thread, so `process` never allocates.
+pub struct LoudnessMeter {
+    sample_rate: f32,
+    // per channel
+    filters: Vec<[Biquad; 2]>,
+    weights: Vec<f64>,
+
+    step_length: usize,
+    step_position: usize,
+    step_sum: f64,
+    // the mean (weighted) power of the most recent steps, circular
+    steps: [f64; SHORT_TERM_STEPS],
+    step_index: usize,
+    steps_filled: usize,
+
+    // momentary (400 ms) blocks, for the integrated loudness
+    blocks: Histogram,
+    // short-term (3 s) blocks, for the loudness range
+    short_term_blocks: Histogram,
+
+    readings: Arc<LoudnessReadings>,
+}
+
+impl LoudnessMeter {
+    /// Start measuring from scratch, clearing the readings.
+    pub fn new(num_channels: usize, sample_rate: f32, readings: Arc<LoudnessReadings>) -> Self {
+        readings.clear();
+        Self {
+            sample_rate,
+            filters: vec![k_weighting(sample_rate); num_channels],
+            weights: (0..num_channels)
+                .map(|channel| channel_weight(channel, num_channels))
+                .collect(),
+            step_length: ((sample_rate * STEP_SECONDS) as usize).max(1),
+            step_position: 0,
+            step_sum: 0.0,
+            steps: [0.0; SHORT_TERM_STEPS],
+            step_index: 0,
+            steps_filled: 0,
+            blocks: Histogram::new(),
+            short_term_blocks: Histogram::new(),
+            readings,
+        }
+    }
+
+    /// Whether this meter can keep measuring a bus with this layout and sample rate.
+    pub fn matches(&self, num_channels: usize, sample_rate: f32) -> bool {
+        self.filters.len() == num_channels && self.sample_rate == sample_rate
+    }
+
+    fn reset(&mut self) {
+        for filter in self.filters.iter_mut().flatten() {
+            filter.state = [0.0; 2];
+        }
+        self.step_position = 0;
+        self.step_sum = 0.0;
+        self.steps_filled = 0;
+        self.blocks.clear();
+        self.short_term_blocks.clear();
+        self.readings.clear();
+    }
+
+    /// Measure `num_samples` samples from planar channel buffers.
+    pub fn process<C: AsRef<[f32]>>(&mut self, block: &[C], num_samples: usize) {
+        if self.readings.reset.swap(false, Ordering::Relaxed) {
+            self.reset();
+        }
+
+        for i in 0..num_samples {
+            for ((filters, weight), channel) in
+                self.filters.iter_mut().zip(&self.weights).zip(block)
+            {
+                let x = channel.as_ref()[i] as f64;
+                let y = filters.iter_mut().fold(x, |x, filter| filter.process(x));
+                self.step_sum += weight * y * y;
+            }
+
+            self.step_position += 1;
+            if self.step_position == self.step_length {
+                self.finish_step();
+            }
+        }
+    }
+
+    fn finish_step(&mut self) {
+        self.steps[self.step_index] = self.step_sum / self.step_length as f64;
+        self.step_index = (self.step_index + 1) % SHORT_TERM_STEPS;
+        self.steps_filled = (self.steps_filled + 1).min(SHORT_TERM_STEPS);
+        self.step_position = 0;
+        self.step_sum = 0.0;
+
+        if self.steps_filled >= MOMENTARY_STEPS {
+            let momentary = self.mean_power(MOMENTARY_STEPS);
+            self.blocks.add(momentary);
+            store(&self.readings.momentary, Some(power_to_lufs(momentary)));
+            store(&self.readings.integrated, self.blocks.integrated());
+        }
+        if self.steps_filled >= SHORT_TERM_STEPS {
+            let short_term = self.mean_power(SHORT_TERM_STEPS);
+            self.short_term_blocks.add(short_term);
+            store(&self.readings.short_term, Some(power_to_lufs(short_term)));
+            store(&self.readings.range, self.short_term_blocks.range());
+        }
+    }
+
+    /// The mean power of the last `steps` steps.
+    fn mean_power(&self, steps: usize) -> f64 {
+        (1..=steps)
+            .map(|back| self.steps[(self.step_index + SHORT_TERM_STEPS - back) % SHORT_TERM_STEPS])
+            .sum::<f64>()
+            / steps as f64
+    }
+}
+
+fn store(reading: &AtomicF32, value: Option<f64>) {
+    let value = value.map_or(f32::NEG_INFINITY, |value| value as f32);
+    reading.store(value, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the test signals from EBU Tech 3341 and 3342 are stereo 1 kHz sines
+    const SAMPLE_RATE: f32 = 48_000.0;
+    const FREQUENCY: f64 = 1_000.0; // hz
+
+    fn new_meter() -> (LoudnessMeter, Arc<LoudnessReadings>) {
+        let readings = Arc::new(LoudnessReadings::default());
+        (
+            LoudnessMeter::new(2, SAMPLE_RATE, readings.clone()),
+            readings,
+        )
+    }
+
+    /// Feed a sine with a peak level of `dbfs` to both channels. Every segment starts at phase 0,
+    /// so segments that are whole milliseconds long join up without a glitch.
+    fn feed(meter: &mut LoudnessMeter, dbfs: f32, seconds: f32) {
+        let gain = 10f64.powf(dbfs as f64 / 20.0);
+        let samples: Vec<f32> = (0..(seconds * SAMPLE_RATE).round() as usize)
+            .map(|i| (gain * (2.0 * PI * FREQUENCY * i as f64 / SAMPLE_RATE as f64).sin()) as f32)
+            .collect();
+        meter.process(&[&samples, &samples], samples.len());
+    }
+
+    fn load(reading: &AtomicF32) -> f32 {
+        reading.load(Ordering::Relaxed)
+    }
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {expected} ± {tolerance}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn reference_sine() {
+        // Tech 3341, case 1
+        let (mut meter, readings) = new_meter();
+        feed(&mut meter, -23.0, 20.0);
+
+        assert_near(load(&readings.momentary), -23.0, 0.1);
+        assert_near(load(&readings.short_term), -23.0, 0.1);
+        assert_near(load(&readings.integrated), -23.0, 0.1);
+    }
+
+    #[test]
+    fn integrated_gating() {
+        // Tech 3341, case 3: the quiet passages fall below the relative gate
+        let (mut meter, readings) = new_meter();
+        for (dbfs, seconds) in [(-36.0, 10.0), (-23.0, 60.0), (-36.0, 10.0)] {
+            feed(&mut meter, dbfs, seconds);
+        }
+        assert_near(load(&readings.integrated), -23.0, 0.1);
+
+        // Tech 3341, case 4: and the near-silent ones below the absolute gate
+        let (mut meter, readings) = new_meter();
+        for (dbfs, seconds) in [
+            (-72.0, 10.0),
+            (-36.0, 10.0),
+            (-23.0, 60.0),
+            (-36.0, 10.0),
+            (-72.0, 10.0),
+        ] {
+            feed(&mut meter, dbfs, seconds);
+        }
+        assert_near(load(&readings.integrated), -23.0, 0.1);
+    }
+
+    #[test]
+    fn silence_is_not_measured() {
+        let (mut meter, readings) = new_meter();
+        feed(&mut meter, f32::NEG_INFINITY, 5.0);
+
+        assert!(load(&readings.momentary) < ABSOLUTE_GATE as f32);
+        assert_eq!(load(&readings.integrated), f32::NEG_INFINITY);
+        assert_eq!(load(&readings.range), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn measurement_windows() {
+        let (mut meter, readings) = new_meter();
+
+        // nothing until the first 400 ms window is full
+        feed(&mut meter, -23.0, 0.3);
+        assert_eq!(load(&readings.momentary), f32::NEG_INFINITY);
+        feed(&mut meter, -23.0, 0.1);
+        assert_near(load(&readings.momentary), -23.0, 0.1);
+        assert_eq!(load(&readings.short_term), f32::NEG_INFINITY);
+
+        // or the first 3 s window
+        feed(&mut meter, -23.0, 2.6);
+        assert_near(load(&readings.short_term), -23.0, 0.1);
+
+        // after a second of silence, the momentary window holds none of the sine,
+        // and the short-term window a third less of it
+        feed(&mut meter, f32::NEG_INFINITY, 1.0);
+        assert!(load(&readings.momentary) < ABSOLUTE_GATE as f32);
+        assert_near(
+            load(&readings.short_term),
+            -23.0 + 10.0 * (2.0f32 / 3.0).log10(),
+            0.1,
+        );
+    }
+
+    #[test]
+    fn loudness_range() {
+        // Tech 3342, cases 1 to 4
+        let cases: [(&[(f32, f32)], f32); 4] = [
+            (&[(-20.0, 20.0), (-30.0, 20.0)], 10.0),
+            (&[(-20.0, 20.0), (-15.0, 20.0)], 5.0),
+            (&[(-40.0, 20.0), (-20.0, 20.0)], 20.0),
+            (
+                &[
+                    (-50.0, 20.0),
+                    (-35.0, 20.0),
+                    (-20.0, 20.0),
+                    (-35.0, 20.0),
+                    (-50.0, 20.0),
+                ],
+                15.0,
+            ),
+        ];
+
+        for (segments, expected) in cases {
+            let (mut meter, readings) = new_meter();
+            for &(dbfs, seconds) in segments {
+                feed(&mut meter, dbfs, seconds);
+            }
+            assert_near(load(&readings.range), expected, 1.0);
+        }
+    }
+}
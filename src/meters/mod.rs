@@ -0,0 +1,6 @@
+//! Meters that run on the audio thread, so they keep measuring while the editor is closed.
+//!
+//! Each meter publishes its readings through atomics, which the editor reads whenever it draws.
+
+pub mod loudness;
+pub mod true_peak;
@@ -0,0 +1,235 @@
+//! Sample-peak and true-peak metering, following ITU-R BS.1770-4 (Annex 2).
+
+use nih_plug::{prelude::AtomicF32, util::db_to_gain};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+// the most channels any of the plugin's layouts has
+pub const MAX_CHANNELS: usize = 12;
+
+const DEFAULT_CEILING: f32 = -1.0; // dbtp
+
+// the 4x oversampling interpolator from BS.1770-4, split into its four phases
+const TAPS: usize = 12;
+#[rustfmt::skip]
+const PHASES: [[f32; TAPS]; 4] = [
+    [
+        0.001708984375, 0.010986328125, -0.0196533203125, 0.033203125,
+        -0.0594482421875, 0.1373291015625, 0.97216796875, -0.102294921875,
+        0.047607421875, -0.026611328125, 0.014892578125, -0.00830078125,
+    ],
+    [
+        -0.0291748046875, 0.029296875, -0.0517578125, 0.089111328125,
+        -0.16650390625, 0.465087890625, 0.77978515625, -0.2003173828125,
+        0.1015625, -0.0582275390625, 0.0330810546875, -0.0189208984375,
+    ],
+    [
+        -0.0189208984375, 0.0330810546875, -0.0582275390625, 0.1015625,
+        -0.2003173828125, 0.77978515625, 0.465087890625, -0.16650390625,
+        0.089111328125, -0.0517578125, 0.029296875, -0.0291748046875,
+    ],
+    [
+        -0.00830078125, 0.014892578125, -0.026611328125, 0.047607421875,
+        -0.102294921875, 0.97216796875, 0.1373291015625, -0.0594482421875,
+        0.033203125, -0.0196533203125, 0.010986328125, 0.001708984375,
+    ],
+];
+
+/// One channel's readings. Peaks are linear.
+#[derive(Default)]
+pub struct ChannelPeaks {
+    // the highest peaks since the editor last read them
+    pub sample_peak: AtomicF32,
+    pub true_peak: AtomicF32,
+    // the highest peaks since the last reset
+    pub sample_peak_hold: AtomicF32,
+    pub true_peak_hold: AtomicF32,
+    // how many samples went over the ceiling since the last reset
+    pub overs: AtomicUsize,
+}
+
+/// The latest readings, shared with the editor.
+pub struct PeakReadings {
+    pub channels: [ChannelPeaks; MAX_CHANNELS],
+    pub num_channels: AtomicUsize,
+    // dbtp, set by the editor
+    pub ceiling: AtomicF32,
+}
+
+impl Default for PeakReadings {
+    fn default() -> Self {
+        Self {
+            channels: Default::default(),
+            num_channels: AtomicUsize::new(0),
+            ceiling: AtomicF32::new(DEFAULT_CEILING),
+        }
+    }
+}
+
+impl PeakReadings {
+    /// Clear the held peaks and the overs.
+    pub fn reset(&self) {
+        for channel in &self.channels {
+            channel.sample_peak_hold.store(0.0, Ordering::Relaxed);
+            channel.true_peak_hold.store(0.0, Ordering::Relaxed);
+            channel.overs.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Measures the peaks of every channel of a bus. This runs on the audio thread.
+pub struct TruePeakMeter {
+    // the most recent samples of each channel, oldest first
+    histories: Vec<[f32; TAPS]>,
+    readings: Arc<PeakReadings>,
+}
+
+impl TruePeakMeter {
+    /// Start measuring from scratch, clearing the held peaks and the overs.
+    pub fn new(num_channels: usize, readings: Arc<PeakReadings>) -> Self {
+        let num_channels = num_channels.min(MAX_CHANNELS);
+        readings.num_channels.store(num_channels, Ordering::Relaxed);
+        readings.reset();
+
+        Self {
+            histories: vec![[0.0; TAPS]; num_channels],
+            readings,
+        }
+    }
+
+    /// Whether this meter can keep measuring a bus with this many channels.
+    pub fn matches(&self, num_channels: usize) -> bool {
+        self.histories.len() == num_channels.min(MAX_CHANNELS)
+    }
+
+    /// Measure `num_samples` samples from planar channel buffers.
+    pub fn process<C: AsRef<[f32]>>(&mut self, block: &[C], num_samples: usize) {
+        let ceiling = db_to_gain(self.readings.ceiling.load(Ordering::Relaxed));
+
+        for ((history, channel), readings) in self
+            .histories
+            .iter_mut()
+            .zip(block)
+            .zip(&self.readings.channels)
+        {
+            let mut sample_peak = 0.0f32;
+            let mut true_peak = 0.0f32;
+            let mut overs = 0;
+
+            for sample in &channel.as_ref()[..num_samples] {
+                history.copy_within(1.., 0);
+                history[TAPS - 1] = *sample;
+
+                // the sample itself counts too, so the true peak is never below the sample peak
+                let mut peak = sample.abs();
+                for phase in &PHASES {
+                    let interpolated: f32 =
+                        phase.iter().zip(history.iter()).map(|(c, x)| c * x).sum();
+                    peak = peak.max(interpolated.abs());
+                }
+
+                sample_peak = sample_peak.max(sample.abs());
+                true_peak = true_peak.max(peak);
+                if peak > ceiling {
+                    overs += 1;
+                }
+            }
+
+            readings
+                .sample_peak
+                .fetch_max(sample_peak, Ordering::Relaxed);
+            readings.true_peak.fetch_max(true_peak, Ordering::Relaxed);
+            readings
+                .sample_peak_hold
+                .fetch_max(sample_peak, Ordering::Relaxed);
+            readings
+                .true_peak_hold
+                .fetch_max(true_peak, Ordering::Relaxed);
+            readings.overs.fetch_add(overs, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2};
+
+    /// A sine at a quarter of the sample rate, sampled 45° away from its peaks,
+    /// so every sample is at ±1 but the signal in between reaches ±√2 (+3 dB).
+    fn quarter_rate_sine(num_samples: usize) -> Vec<f32> {
+        (0..num_samples)
+            .map(|i| SQRT_2 * (FRAC_PI_2 * i as f32 + FRAC_PI_4).sin())
+            .collect()
+    }
+
+    fn new_meter(ceiling: f32) -> (TruePeakMeter, Arc<PeakReadings>) {
+        let readings = Arc::new(PeakReadings::default());
+        readings.ceiling.store(ceiling, Ordering::Relaxed);
+        (TruePeakMeter::new(1, readings.clone()), readings)
+    }
+
+    fn load_db(reading: &AtomicF32) -> f32 {
+        20.0 * reading.load(Ordering::Relaxed).log10()
+    }
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "expected {expected} ± {tolerance}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn inter_sample_peaks() {
+        let (mut meter, readings) = new_meter(DEFAULT_CEILING);
+        meter.process(&[quarter_rate_sine(1024)], 1024);
+
+        let channel = &readings.channels[0];
+        assert_near(load_db(&channel.sample_peak), 0.0, 0.01);
+        assert_near(load_db(&channel.true_peak), 3.01, 0.2);
+        assert_near(load_db(&channel.sample_peak_hold), 0.0, 0.01);
+        assert_near(load_db(&channel.true_peak_hold), 3.01, 0.2);
+    }
+
+    #[test]
+    fn overs() {
+        const NUM_SAMPLES: usize = 1000;
+
+        // the sine peaks between every other pair of samples, so with the ceiling between the
+        // sample peak and the true peak, half of the samples are over (once the interpolator is
+        // primed), and with it above the true peak, none are
+        for (ceiling, expected) in [(1.0, NUM_SAMPLES / 2), (4.0, 0)] {
+            let (mut meter, readings) = new_meter(ceiling);
+            meter.process(&[quarter_rate_sine(TAPS)], TAPS);
+            readings.reset();
+
+            // (starting on the same phase the priming left off at)
+            let signal = quarter_rate_sine(TAPS + NUM_SAMPLES);
+            meter.process(&[&signal[TAPS..]], NUM_SAMPLES);
+            assert_eq!(
+                readings.channels[0].overs.load(Ordering::Relaxed),
+                expected,
+                "with a ceiling of {ceiling} dBTP"
+            );
+        }
+    }
+
+    #[test]
+    fn reset_clears_holds() {
+        let (mut meter, readings) = new_meter(DEFAULT_CEILING);
+        meter.process(&[quarter_rate_sine(1024)], 1024);
+        readings.reset();
+
+        let channel = &readings.channels[0];
+        assert_eq!(channel.sample_peak_hold.load(Ordering::Relaxed), 0.0);
+        assert_eq!(channel.true_peak_hold.load(Ordering::Relaxed), 0.0);
+        assert_eq!(channel.overs.load(Ordering::Relaxed), 0);
+
+        // and they pick up from there
+        meter.process(&[vec![0.5; 64]], 64);
+        assert_near(load_db(&channel.sample_peak_hold), -6.02, 0.01);
+    }
+}